@@ -4,8 +4,8 @@ use heck::AsPascalCase;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote, quote_spanned};
 use syn::{
-    Data, DeriveInput, Error, Expr, FnArg, Ident, ItemFn, ItemStruct, LitBool, LitStr, Meta, Pat,
-    Path, Result, Token, Type,
+    Data, DeriveInput, Error, Expr, FnArg, Ident, ItemFn, ItemStruct, LitBool, LitInt, LitStr,
+    Meta, Pat, Path, Result, Token, Type,
     parse::{Parse, ParseStream},
     parse_macro_input,
     punctuated::Punctuated,
@@ -108,6 +108,32 @@ struct HandlerArgs {
     command: Option<LitStr>,
     echo_cmd: bool,
     help_msg: Option<String>,
+    permission: Option<Ident>,
+    cooldown_secs: Option<i64>,
+    daily_quota: Option<u32>,
+    scope: Option<Ident>,
+    args: Option<Path>,
+    aliases: Vec<LitStr>,
+    reply: Option<Ident>,
+}
+
+/// 解析 "30s"、"5m"、"2h"、"1d" 形式的时长，返回秒数
+fn parse_duration(lit: &LitStr) -> syn::Result<i64> {
+    let value = lit.value();
+    let (num, unit) = value.split_at(value.len().saturating_sub(1));
+    let num = num
+        .parse::<i64>()
+        .map_err(|_| syn::Error::new_spanned(lit, "Invalid duration, expected e.g. \"30s\""))?;
+    match unit {
+        "s" => Ok(num),
+        "m" => Ok(num * 60),
+        "h" => Ok(num * 3600),
+        "d" => Ok(num * 86400),
+        _ => Err(syn::Error::new_spanned(
+            lit,
+            "Invalid duration unit, expected 's', 'm', 'h' or 'd'",
+        )),
+    }
 }
 
 impl Parse for HandlerArgs {
@@ -116,6 +142,13 @@ impl Parse for HandlerArgs {
         let mut command = None;
         let mut echo_cmd = false;
         let mut help_msg = None;
+        let mut permission = None;
+        let mut cooldown_secs = None;
+        let mut daily_quota = None;
+        let mut scope = None;
+        let mut args = None;
+        let mut aliases = Vec::new();
+        let mut reply = None;
 
         let pairs = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
         for meta in pairs {
@@ -143,10 +176,75 @@ impl Parse for HandlerArgs {
                     let val = syn::parse2::<LitStr>(quote!(#expr))?;
                     help_msg = Some(val.value());
                 }
+            } else if path.is_ident("permission") {
+                if let Meta::NameValue(nv) = meta {
+                    let expr = nv.value;
+                    permission = Some(syn::parse2::<Ident>(quote!(#expr))?);
+                }
+            } else if path.is_ident("aliases") {
+                if let Meta::NameValue(nv) = meta {
+                    let Expr::Array(array) = nv.value else {
+                        return Err(syn::Error::new_spanned(
+                            nv.value,
+                            "The 'aliases' attribute expects an array, e.g. aliases = [\"下载\", \"dl\"]",
+                        ));
+                    };
+                    for elem in array.elems {
+                        aliases.push(syn::parse2::<LitStr>(quote!(#elem))?);
+                    }
+                }
+            } else if path.is_ident("args") {
+                if let Meta::NameValue(nv) = meta {
+                    let expr = nv.value;
+                    args = Some(syn::parse2::<Path>(quote!(#expr))?);
+                }
+            } else if path.is_ident("cooldown") {
+                if let Meta::NameValue(nv) = meta {
+                    let expr = nv.value;
+                    let lit = syn::parse2::<LitStr>(quote!(#expr))?;
+                    cooldown_secs = Some(parse_duration(&lit)?);
+                }
+            } else if path.is_ident("daily_quota") {
+                if let Meta::NameValue(nv) = meta {
+                    let expr = nv.value;
+                    let lit = syn::parse2::<LitInt>(quote!(#expr))?;
+                    daily_quota = Some(lit.base10_parse::<u32>()?);
+                }
+            } else if path.is_ident("scope") {
+                if let Meta::NameValue(nv) = meta {
+                    let expr = nv.value;
+                    let lit = syn::parse2::<LitStr>(quote!(#expr))?;
+                    scope = Some(match lit.value().as_str() {
+                        "user" => format_ident!("User"),
+                        "group" => format_ident!("Group"),
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "Invalid scope, expected 'user' or 'group'",
+                            ));
+                        }
+                    });
+                }
+            } else if path.is_ident("reply") {
+                if let Meta::NameValue(nv) = meta {
+                    let expr = nv.value;
+                    let lit = syn::parse2::<LitStr>(quote!(#expr))?;
+                    reply = Some(match lit.value().as_str() {
+                        "forward" => format_ident!("Forward"),
+                        "plain" => format_ident!("Plain"),
+                        "quote" => format_ident!("Quote"),
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "Invalid reply style, expected 'forward', 'plain' or 'quote'",
+                            ));
+                        }
+                    });
+                }
             } else {
                 return Err(syn::Error::new_spanned(
                     path,
-                    "Unknown attribute key, expected 'msg_type', 'command', 'echo_cmd', 'help_msg'",
+                    "Unknown attribute key, expected 'msg_type', 'command', 'echo_cmd', 'help_msg', 'permission', 'cooldown', 'daily_quota', 'scope', 'args', 'aliases', 'reply'",
                 ));
             }
         }
@@ -165,12 +263,54 @@ impl Parse for HandlerArgs {
             ));
         }
 
-        if let Some(ref cmd) = command
-            && cmd.value().len() < 2
+        if !aliases.is_empty() && command.is_none() {
+            return Err(syn::Error::new_spanned(
+                &aliases[0],
+                "The 'aliases' attribute can only be used together with 'command'",
+            ));
+        }
+
+        // 指令按空白切分后整体匹配，名称中不能出现空白
+        for name in command.iter().chain(aliases.iter()) {
+            let value = name.value();
+            if value.is_empty() || value.contains(char::is_whitespace) {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "Command names and aliases must be non-empty and contain no whitespace.",
+                ));
+            }
+        }
+
+        if let Some(ref perm) = permission {
+            if !["Anyone", "GroupAdmin", "Owner", "BotAdmin"].contains(&perm.to_string().as_str()) {
+                return Err(syn::Error::new_spanned(
+                    perm,
+                    "Unknown permission, expected 'Anyone', 'GroupAdmin', 'Owner' or 'BotAdmin'",
+                ));
+            }
+            if command.is_none() {
+                return Err(syn::Error::new_spanned(
+                    perm,
+                    "The 'permission' attribute can only be used together with 'command'",
+                ));
+            }
+        }
+
+        if (cooldown_secs.is_some() || daily_quota.is_some() || scope.is_some())
+            && command.is_none()
+        {
+            return Err(syn::Error::new_spanned(
+                &msg_type,
+                "The 'cooldown', 'daily_quota' and 'scope' attributes can only be used together with 'command'",
+            ));
+        }
+
+        if let Some(ref args) = args
+            && (command.is_none() || !msg_type.as_ref().is_some_and(|t| *t == "Message"))
         {
             return Err(syn::Error::new_spanned(
-                cmd,
-                "The 'command' attribute must be at least 2 characters long.",
+                args,
+                "The 'args' attribute requires 'command' and 'msg_type = Message'",
             ));
         }
 
@@ -179,6 +319,13 @@ impl Parse for HandlerArgs {
             command,
             echo_cmd,
             help_msg,
+            permission,
+            cooldown_secs,
+            daily_quota,
+            scope,
+            args,
+            aliases,
+            reply,
         })
     }
 }
@@ -214,6 +361,75 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
         quote! { None }
     };
 
+    let permission = args
+        .permission
+        .clone()
+        .unwrap_or_else(|| format_ident!("Anyone"));
+
+    let limit_const = if args.cooldown_secs.is_some() || args.daily_quota.is_some() {
+        let name = fn_name.to_string();
+        let cooldown = args.cooldown_secs.unwrap_or(0);
+        let quota = match args.daily_quota {
+            Some(q) => quote! { Some(#q) },
+            None => quote! { None },
+        };
+        let scope = args.scope.clone().unwrap_or_else(|| format_ident!("User"));
+        quote! {
+            Some(RateLimit {
+                name: #name,
+                cooldown_secs: #cooldown,
+                daily_quota: #quota,
+                scope: LimitScope::#scope,
+            })
+        }
+    } else {
+        quote! { None }
+    };
+
+    // 参数变量名取自函数的第二个参数，默认为 args
+    // 参数在占用冷却与配额之前解析，解析失败不计入调用次数
+    let (args_parse, args_param, args_pass) = if let Some(ref args_ty) = args.args {
+        let args_ident = input_fn
+            .sig
+            .inputs
+            .iter()
+            .nth(1)
+            .and_then(|arg| match arg {
+                FnArg::Typed(pat) => match pat.pat.as_ref() {
+                    Pat::Ident(ident) => Some(ident.ident.clone()),
+                    _ => None,
+                },
+                FnArg::Receiver(_) => None,
+            })
+            .unwrap_or_else(|| format_ident!("args"));
+        let cmd = args.command.as_ref().unwrap();
+        let args_parse = quote! {
+            let command = format!("{}{}", crate::config::current().bot.command_prefix, #cmd);
+            let #args_ident: #args_ty = match crate::abi::router::args::command_tokens(&ctx.message)
+                .and_then(<#args_ty as crate::abi::router::args::CommandArgs>::parse)
+            {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    ctx.send_message_async(crate::abi::message::from_str(format!(
+                        "参数错误: {}\n用法: {} {}",
+                        e,
+                        command,
+                        <#args_ty as crate::abi::router::args::CommandArgs>::USAGE
+                    )));
+                    crate::shutdown::global().spawn(ctx.finish());
+                    return Ok(());
+                }
+            };
+        };
+        (
+            args_parse,
+            quote! { , #args_ident: #args_ty },
+            quote! { , #args_ident },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
     let echo_logic = if args.echo_cmd {
         quote! {
             {
@@ -228,6 +444,14 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     };
 
+    let reply_logic = if let Some(ref style) = args.reply {
+        quote! {
+            ctx.set_reply_style(crate::abi::router::settings::ReplyStyle::#style);
+        }
+    } else {
+        quote! {}
+    };
+
     let (generics, target_type) = if args.msg_type.is_some() {
         (quote! { <T> }, quote! { #target_type_ident })
     } else {
@@ -240,11 +464,23 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
     let help_trait = if args.command.is_some() {
         let cmd_val = args.command.as_ref().unwrap().value();
         let help_val = args.help_msg.as_ref().unwrap();
-        let help_msg = format!("指令: {}\n{}\n\n", cmd_val, help_val);
+        let alias_vals = args.aliases.iter().map(LitStr::value).collect::<Vec<_>>();
+        let help_msg = if alias_vals.is_empty() {
+            format!("指令: {}\n{}\n\n", cmd_val, help_val)
+        } else {
+            format!(
+                "指令: {} (别名: {})\n{}\n\n",
+                cmd_val,
+                alias_vals.join(", "),
+                help_val
+            )
+        };
 
         quote! {
             impl BuildHelp for #struct_name {
                 const HELP_MSG: &'static str = #help_msg;
+                const COMMANDS: &'static [&'static str] = &[#cmd_val, #( #alias_vals ),*];
+                const PERMISSION: Permission = Permission::#permission;
             }
         }
     } else {
@@ -255,7 +491,7 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[allow(non_upper_case_globals)]
         #vis const #fn_name: #struct_name = #struct_name;
 
-        async fn #hidden_impl #generics(mut ctx: Context<T, #target_type>) -> anyhow::Result<()>
+        async fn #hidden_impl #generics(mut ctx: Context<T, #target_type> #args_param) -> anyhow::Result<()>
         where T: BotClient + BotHandler + std::fmt::Debug + 'static
         {
             let result: anyhow::Result<()> = (async { #body }).await;;
@@ -276,16 +512,44 @@ pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
         {
             const FILTER_TYPE: Option<Type> = #type_const;
             const FILTER_CMD: Option<&'static str> = #cmd_const;
+            const PERMISSION: Permission = Permission::#permission;
+            const LIMIT: Option<RateLimit> = #limit_const;
 
             #[inline(always)] //因为后面设计复杂的匹配逻辑并且强依赖死代码消除(DCE)所以这里强制内联
             fn handle(&self, ctx: &Context<T, M>) -> anyhow::Result<()> {
-                let ctx = ctx.clone();
-                let typed_ctx = unsafe {
+                // 群设置中未启用或处于免打扰时段的指令直接忽略
+                if let Some(name) = <Self as Handler<T, M>>::FILTER_CMD
+                    && !crate::abi::router::settings::allows_command(ctx.target, name)
+                {
+                    tracing::debug!("指令 {} 在 {:?} 中未启用", name, ctx.target);
+                    return Ok(());
+                }
+                let mut ctx = ctx.clone();
+                #reply_logic
+                if !<Self as Handler<T, M>>::PERMISSION.allows(&ctx.sender) {
+                    ctx.send_message_async(crate::abi::message::from_str(
+                        <Self as Handler<T, M>>::PERMISSION.refusal(),
+                    ));
+                    crate::shutdown::global().spawn(ctx.finish());
+                    return Ok(());
+                }
+                let mut ctx = unsafe {
                     std::mem::transmute::<Context<T, M>, Context<T, #target_type_ident>>(ctx)
                 };
+                #args_parse
+                if let Some(limit) = <Self as Handler<T, M>>::LIMIT
+                    && let Err(refusal) = limit.acquire(&ctx.sender, ctx.target)
+                {
+                    ctx.send_message_async(crate::abi::message::from_str(refusal));
+                    crate::shutdown::global().spawn(ctx.finish());
+                    return Ok(());
+                }
+                let span = ctx.span.clone();
+                let typed_ctx = ctx;
                 let handle_ctx = #echo_logic;
 
-                tokio::spawn(#hidden_impl(handle_ctx));
+                // 停机时会等待这里启动的 handler 结束
+                crate::shutdown::global().spawn(tracing::Instrument::instrument(#hidden_impl(handle_ctx #args_pass), span));
 
                 Ok(())
             }
@@ -341,6 +605,7 @@ pub fn register_handler_with_help(input: TokenStream) -> TokenStream {
     let mut all_cmds = cmd_handlers.clone();
     let help_handler_path: Path = syn::parse_str("HelpHandler").unwrap();
     all_cmds.push(help_handler_path);
+    let cmd_indices = (0..all_cmds.len()).map(proc_macro2::Literal::usize_unsuffixed);
 
     let expanded = quote! {
         #[handler(
@@ -352,14 +617,37 @@ pub fn register_handler_with_help(input: TokenStream) -> TokenStream {
         pub async fn help<T>(ctx: Context<T, Help>) -> anyhow::Result<()>
         where T: BotClient + BotHandler + std::fmt::Debug + 'static
         {
-            const ALL_HELP: &'static str = const_format::concatcp!(
-                HelpHandler::HELP_MSG, "\n",
-                #( <#cmd_handlers as BuildHelp>::HELP_MSG, "\n", )*
-            );
-            ctx.send_message_async(crate::abi::message::from_str(ALL_HELP));
+            const ALL_HELP: &[(Permission, &'static str)] = &[
+                (<HelpHandler as BuildHelp>::PERMISSION, <HelpHandler as BuildHelp>::HELP_MSG),
+                #( (<#cmd_handlers as BuildHelp>::PERMISSION, <#cmd_handlers as BuildHelp>::HELP_MSG), )*
+            ];
+            // 只列出调用者有权限执行的指令
+            let help_text = ALL_HELP
+                .iter()
+                .filter(|(permission, _)| permission.allows(&ctx.sender))
+                .map(|(_, msg)| format!("{}\n", msg))
+                .collect::<String>();
+            ctx.send_message_async(crate::abi::message::from_str(help_text));
             Ok(())
         }
 
+        const COMMAND_COUNT: usize = crate::abi::router::command::count(&[
+            #( <#all_cmds as BuildHelp>::COMMANDS, )*
+        ]);
+        // 指令名冲突时在此处编译失败
+        const COMMAND_TABLE: [(&'static str, usize); COMMAND_COUNT] = crate::abi::router::command::build(&[
+            #( <#all_cmds as BuildHelp>::COMMANDS, )*
+        ]);
+        const COMMAND_NAMES: &[&'static str] = &[
+            #( <#all_cmds as BuildHelp>::COMMANDS[0], )*
+        ];
+
+        /// 指令名或别名对应的指令名
+        #[allow(dead_code)]
+        pub fn command_name(token: &str) -> Option<&'static str> {
+            crate::abi::router::command::lookup(&COMMAND_TABLE, token).map(|idx| COMMAND_NAMES[idx])
+        }
+
         #[allow(non_snake_case, dead_code)]
         pub fn dispatch_all_handlers<T, M>(context: Context<T, M>)
         where
@@ -371,20 +659,16 @@ pub fn register_handler_with_help(input: TokenStream) -> TokenStream {
 
             match msg_type {
                 Type::Message => {
-                    let prefix = config::get_command_prefix();
-                    let prefix_len = prefix.len();
+                    let config = config::current();
 
-                    if text.starts_with(prefix) && text.len() >= prefix_len + 2 {
-                        let cmd_part = &text[prefix_len..];
-                        let b = cmd_part.as_bytes();
+                    if let Some(cmd_part) = text.strip_prefix(config.bot.command_prefix.as_str()) {
+                        let (token, _) = crate::abi::router::command::split_command(cmd_part);
 
-                        match (b[0], b[1]) {
+                        match crate::abi::router::command::lookup(&COMMAND_TABLE, token) {
                             #(
-                                (b1, b2) if [b1, b2] == *<#all_cmds as Handler<T, M>>::FILTER_CMD.unwrap().as_bytes().get(0..2).unwrap_or(&[0,0]) => {
-                                    if cmd_part.starts_with(<#all_cmds as Handler<T, M>>::FILTER_CMD.unwrap()) {
-                                        let _ = <#all_cmds as Handler<T, M>>::handle(&#all_cmds, &context);
-                                        return;
-                                    }
+                                Some(#cmd_indices) => {
+                                    let _ = <#all_cmds as Handler<T, M>>::handle(&#all_cmds, &context);
+                                    return;
                                 }
                             )*
                             _ => {}
@@ -1206,3 +1490,228 @@ pub fn box_new(input: TokenStream) -> TokenStream {
         .into(),
     }
 }
+
+/// 若类型为 `Option<T>` 返回 `T`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    if let Type::Path(tp) = ty
+        && let Some(last_seg) = tp.path.segments.last()
+        && last_seg.ident == "Option"
+        && let syn::PathArguments::AngleBracketed(args) = &last_seg.arguments
+        && let Some(syn::GenericArgument::Type(inner)) = args.args.first()
+    {
+        return Some(inner);
+    }
+    None
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(tp) if tp.path.is_ident("bool"))
+}
+
+#[derive(Default)]
+struct ArgAttr {
+    long: Option<String>,
+    short: Option<char>,
+    default: Option<Expr>,
+    rest: bool,
+}
+
+fn parse_arg_attr(field: &syn::Field, field_name: &str) -> Result<ArgAttr> {
+    let mut attr = ArgAttr::default();
+    for a in &field.attrs {
+        if !a.path().is_ident("arg") {
+            continue;
+        }
+        a.parse_nested_meta(|meta| {
+            if meta.path.is_ident("long") {
+                attr.long = Some(if meta.input.peek(Token![=]) {
+                    meta.value()?.parse::<LitStr>()?.value()
+                } else {
+                    field_name.replace('_', "-")
+                });
+            } else if meta.path.is_ident("short") {
+                attr.short = Some(meta.value()?.parse::<syn::LitChar>()?.value());
+            } else if meta.path.is_ident("default") {
+                let lit = meta.value()?.parse::<LitStr>()?;
+                attr.default = Some(lit.parse::<Expr>()?);
+            } else if meta.path.is_ident("rest") {
+                attr.rest = true;
+            } else {
+                return Err(meta.error(
+                    "Unknown arg attribute, expected 'long', 'short', 'default' or 'rest'",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attr)
+}
+
+/// 将指令后的文本解析为结构体
+///
+/// 未标注的字段按顺序作为位置参数，`#[arg(long)]`/`#[arg(short = 'k')]` 为选项，
+/// `bool` 类型的选项是开关，`Option<T>` 或 `#[arg(default = "expr")]` 的参数可省略，
+/// `#[arg(rest)]` 的 `String` 字段收集剩余的全部位置参数。
+#[proc_macro_derive(CommandArgs, attributes(arg))]
+pub fn derive_command_args(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_command_args(&input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_command_args(input: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(name, "CommandArgs 仅支持结构体"));
+    };
+
+    let mut usage_pos = Vec::new();
+    let mut usage_opt = Vec::new();
+    let mut option_vars = Vec::new();
+    let mut option_arms = Vec::new();
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut field_idents = Vec::new();
+    let mut seen_rest = false;
+
+    for field in &data.fields {
+        let ident = field
+            .ident
+            .as_ref()
+            .ok_or_else(|| Error::new_spanned(field, "CommandArgs 仅支持具名结构体"))?;
+        let field_name = ident.to_string();
+        let ty = &field.ty;
+        let attr = parse_arg_attr(field, &field_name)?;
+        field_idents.push(ident.clone());
+
+        let inner = option_inner(ty);
+        let value_ty = inner.unwrap_or(ty);
+        let optional = inner.is_some() || attr.default.is_some();
+        let missing = match (&attr.default, inner) {
+            (Some(default), _) => quote! { #default },
+            (None, Some(_)) => quote! { None },
+            (None, None) => quote! { return Err(ArgsError::Missing(#field_name)) },
+        };
+        let wrap = if inner.is_some() {
+            quote! { Some(parse_value::<#value_ty>(#field_name, &v)?) }
+        } else {
+            quote! { parse_value::<#value_ty>(#field_name, &v)? }
+        };
+
+        if attr.long.is_some() || attr.short.is_some() {
+            if attr.rest {
+                return Err(Error::new_spanned(field, "选项不能同时标注 rest"));
+            }
+            let mut labels = Vec::new();
+            if let Some(long) = &attr.long {
+                labels.push(format!("--{}", long));
+            }
+            if let Some(short) = attr.short {
+                labels.push(format!("-{}", short));
+            }
+            let label = labels.join("|");
+            let var = format_ident!("__opt_{}", ident);
+
+            if is_bool(ty) {
+                usage_opt.push(format!("[{}]", label));
+                option_vars.push(quote! { let mut #var = false; });
+                option_arms.push(quote! { #( #labels )|* => #var = true, });
+                options.push(quote! { let #ident: bool = #var; });
+            } else {
+                usage_opt.push(if optional {
+                    format!("[{} <{}>]", label, field_name)
+                } else {
+                    format!("{} <{}>", label, field_name)
+                });
+                option_vars.push(quote! { let mut #var: Option<String> = None; });
+                option_arms.push(quote! {
+                    #( #labels )|* => {
+                        #var = Some(tokens.next().ok_or(ArgsError::MissingValue(#label))?);
+                    }
+                });
+                options.push(quote! {
+                    let #ident: #ty = match #var {
+                        Some(v) => #wrap,
+                        None => #missing,
+                    };
+                });
+            }
+            continue;
+        }
+
+        if seen_rest {
+            return Err(Error::new_spanned(field, "rest 参数必须是最后一个位置参数"));
+        }
+        if attr.rest {
+            seen_rest = true;
+            usage_pos.push(if optional {
+                format!("[{}...]", field_name)
+            } else {
+                format!("<{}...>", field_name)
+            });
+            positional.push(quote! {
+                let #ident: #ty = {
+                    let v = positional.by_ref().collect::<Vec<_>>().join(" ");
+                    if v.is_empty() { #missing } else { #wrap }
+                };
+            });
+        } else {
+            usage_pos.push(if optional {
+                format!("[{}]", field_name)
+            } else {
+                format!("<{}>", field_name)
+            });
+            positional.push(quote! {
+                let #ident: #ty = match positional.next() {
+                    Some(v) => #wrap,
+                    None => #missing,
+                };
+            });
+        }
+    }
+
+    let usage = usage_pos
+        .into_iter()
+        .chain(usage_opt)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(quote! {
+        impl crate::abi::router::args::CommandArgs for #name {
+            const USAGE: &'static str = #usage;
+
+            #[allow(unused_mut, unused_variables)]
+            fn parse(tokens: Vec<String>) -> Result<Self, crate::abi::router::args::ArgsError> {
+                use crate::abi::router::args::{ArgsError, parse_value};
+
+                #( #option_vars )*
+                let mut rest = Vec::new();
+                let mut tokens = tokens.into_iter();
+                while let Some(token) = tokens.next() {
+                    match token.as_str() {
+                        #( #option_arms )*
+                        // 负数仍按位置参数处理
+                        s if s.len() > 1
+                            && s.starts_with('-')
+                            && !s[1..].starts_with(|c: char| c.is_ascii_digit()) =>
+                        {
+                            return Err(ArgsError::UnknownOption(token));
+                        }
+                        _ => rest.push(token),
+                    }
+                }
+
+                let mut positional = rest.into_iter();
+                #( #positional )*
+                if let Some(extra) = positional.next() {
+                    return Err(ArgsError::TooMany(extra));
+                }
+                #( #options )*
+
+                Ok(#name { #( #field_idents ),* })
+            }
+        }
+    })
+}
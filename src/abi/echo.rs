@@ -33,6 +33,12 @@ impl Echo {
     }
 }
 
+impl fmt::Display for Echo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl Serialize for Echo {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -67,6 +73,27 @@ impl<'de> Deserialize<'de> for Echo {
     }
 }
 
+/// 与 Napcat 的连接断开时，等待中的 API 调用会以此错误结束
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionLost;
+
+impl fmt::Display for ConnectionLost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("与 Napcat 的连接已断开")
+    }
+}
+
+impl std::error::Error for ConnectionLost {}
+
+/// 丢弃所有等待中的响应通道，使对应的 `EchoPending::wait` 立即返回 `ConnectionLost`
+pub fn echo_fail_all() {
+    let pending = RESPONSE_REGISTRY.len();
+    RESPONSE_REGISTRY.clear();
+    if pending > 0 {
+        debug!("连接断开，已取消 {} 个等待中的 API 调用", pending);
+    }
+}
+
 pub fn echo_send_result(echo: &str, response: Utf8Bytes) {
     if let Ok(echo_id) = echo.parse::<u64>()
         && let Some(entry) = RESPONSE_REGISTRY.remove(&echo_id)
@@ -88,10 +115,16 @@ impl EchoPending {
         Self { echo, receiver: rx }
     }
 
+    /// 请求未能发出时调用，移除已注册的 Echo
+    pub fn cancel(self) {
+        RESPONSE_REGISTRY.remove(&self.echo.0);
+    }
+
     pub async fn wait(self) -> Result<Utf8Bytes> {
         let ret = match time::timeout(TIMEOUT, self.receiver).await {
             Ok(Ok(response)) => Ok(response),
-            Ok(Err(_)) => Err(anyhow::anyhow!("收到的响应通道已关闭")),
+            // 发送端只会在连接断开清理注册表时被直接丢弃
+            Ok(Err(_)) => Err(anyhow::Error::new(ConnectionLost)),
             Err(_) => Err(anyhow::anyhow!("等待 Echo 响应超时")),
         };
 
@@ -102,3 +135,19 @@ impl EchoPending {
         ret
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fail_all() -> Result<()> {
+        let pending = EchoPending::new(Echo::new());
+        echo_fail_all();
+
+        let err = pending.wait().await.unwrap_err();
+        assert!(err.downcast_ref::<ConnectionLost>().is_some());
+
+        Ok(())
+    }
+}
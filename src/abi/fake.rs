@@ -0,0 +1,369 @@
+use crate::abi::{
+    echo::{Echo, echo_send_result},
+    message::{Event, Params, api},
+    network::{self, BotClient},
+    router::handler::{NapcatRouter, Router},
+    websocket::{BotHandler, BotTransport},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::fmt;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc, watch};
+use tokio::time;
+use tokio_tungstenite::tungstenite::Utf8Bytes;
+use tracing::{debug, error};
+
+/// 假 Bot 自身的 QQ 号
+pub const FAKE_SELF_ID: i64 = 10000;
+
+static MESSAGE_ID: AtomicI32 = AtomicI32::new(1);
+
+/// 被记录下来的一次 API 调用
+#[derive(Debug, Clone)]
+pub struct ApiCall {
+    pub action: &'static str,
+    pub params: Value,
+}
+
+/// 未指定 retcode 的失败响应使用的 retcode
+const FAILED_RETCODE: u16 = 100;
+
+type Responder = Box<dyn Fn(&Value) -> Result<Value, (u16, String)> + Send + Sync>;
+
+/// 进程内的假 OneBot 实现，用于在 `cargo test` 中端到端驱动 handler
+///
+/// 注入的事件走真实的解析与路由流程，handler 发出的每个动作都会被记录，
+/// 未编排响应的动作一律返回 `status: ok` 与空的 `data`。
+pub struct FakeBot {
+    handler: mpsc::UnboundedSender<Event>,
+    calls: Mutex<Vec<ApiCall>>,
+    called: Notify,
+    responders: DashMap<&'static str, Responder>,
+    /// 假 Bot 没有真实连接，始终视为已连接，不影响全局的连接状态
+    connected: watch::Sender<bool>,
+}
+
+impl fmt::Debug for FakeBot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FakeBot")
+            .field("calls", &self.calls)
+            .finish_non_exhaustive()
+    }
+}
+
+struct FakeTransport(Arc<FakeBot>);
+
+impl BotTransport<FakeBot> for FakeTransport {
+    fn handler(&self) -> Arc<FakeBot> {
+        self.0.clone()
+    }
+
+    fn disconnect(&mut self) {}
+}
+
+impl FakeBot {
+    pub fn new() -> (Arc<Self>, mpsc::UnboundedReceiver<Event>) {
+        let (tx, rx) = mpsc::unbounded_channel::<Event>();
+        let bot = FakeBot {
+            handler: tx,
+            calls: Mutex::new(Vec::new()),
+            called: Notify::new(),
+            responders: DashMap::new(),
+            connected: watch::Sender::new(true),
+        };
+        (Arc::new(bot), rx)
+    }
+
+    /// 创建假 Bot 并在后台运行一个挂在它上面的 `NapcatRouter`
+    pub fn spawn_router() -> Arc<Self> {
+        Self::spawn_router_with(|_| {})
+    }
+
+    /// 同 `spawn_router`，启动前可对路由器做额外配置（如添加中间件）
+    pub fn spawn_router_with<F>(setup: F) -> Arc<Self>
+    where
+        F: FnOnce(&mut NapcatRouter<FakeBot>),
+    {
+        let (bot, subscribe) = Self::new();
+        let mut router = NapcatRouter::new(subscribe, Box::new(FakeTransport(bot.clone())));
+        setup(&mut router);
+        tokio::spawn(async move {
+            router.run().await;
+        });
+        bot
+    }
+
+    /// 为指定动作编排响应，`Ok` 作为 `data` 返回，`Err` 作为失败信息返回
+    pub fn script<F>(&self, action: &'static str, responder: F)
+    where
+        F: Fn(&Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.script_retcode(action, move |params| {
+            responder(params).map_err(|message| (FAILED_RETCODE, message))
+        });
+    }
+
+    /// 同 `script`，失败时可指定 retcode，如模拟被限流
+    pub fn script_retcode<F>(&self, action: &'static str, responder: F)
+    where
+        F: Fn(&Value) -> Result<Value, (u16, String)> + Send + Sync + 'static,
+    {
+        self.responders.insert(action, Box::new(responder));
+    }
+
+    /// 注入一个事件，与从 Napcat 收到的事件走同一条路径
+    pub fn inject(&self, event: Event) {
+        if let Err(e) = self.handler.send(event) {
+            error!("注入事件失败: {:?}", e);
+        }
+    }
+
+    /// 以原始 JSON 注入事件，会经过真实的事件反序列化
+    pub fn inject_json(&self, event: Value) -> Result<()> {
+        self.inject(network::parse_event(&event.to_string())?);
+        Ok(())
+    }
+
+    pub fn group_message(&self, group_id: i64, user_id: i64, text: &str) -> Result<()> {
+        self.inject_json(json!({
+            "post_type": "message",
+            "message_type": "group",
+            "time": 1700000000,
+            "self_id": FAKE_SELF_ID,
+            "sub_type": "normal",
+            "message_id": MESSAGE_ID.fetch_add(1, Ordering::Relaxed),
+            "group_id": group_id,
+            "user_id": user_id,
+            "anonymous": null,
+            "raw_message": text,
+            "font": 0,
+            "sender": {
+                "user_id": user_id,
+                "nickname": "TestUser",
+                "card": "",
+                "role": "member",
+            },
+            "message": [{ "type": "text", "data": { "text": text } }],
+        }))
+    }
+
+    pub fn private_message(&self, user_id: i64, text: &str) -> Result<()> {
+        self.inject_json(json!({
+            "post_type": "message",
+            "message_type": "private",
+            "time": 1700000000,
+            "self_id": FAKE_SELF_ID,
+            "sub_type": "friend",
+            "message_id": MESSAGE_ID.fetch_add(1, Ordering::Relaxed),
+            "user_id": user_id,
+            "raw_message": text,
+            "font": 0,
+            "sender": {
+                "user_id": user_id,
+                "nickname": "TestUser",
+            },
+            "message": [{ "type": "text", "data": { "text": text } }],
+        }))
+    }
+
+    /// 目前为止记录到的全部调用
+    pub fn calls(&self) -> Vec<ApiCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// 等待某个动作第一次被调用，超时返回 `None`
+    pub async fn wait_for(&self, action: &str, timeout: Duration) -> Option<ApiCall> {
+        time::timeout(timeout, async {
+            loop {
+                let notified = self.called.notified();
+                if let Some(call) = self
+                    .calls
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .find(|c| c.action == action)
+                {
+                    return call.clone();
+                }
+                notified.await;
+            }
+        })
+        .await
+        .ok()
+    }
+}
+
+#[async_trait]
+impl BotClient for FakeBot {
+    async fn call_api<T: Params + Serialize + fmt::Debug>(
+        &self,
+        params: T,
+        echo: Echo,
+    ) -> Result<api::ApiResponsePending<T::Response>> {
+        let action = T::ACTION;
+        let params = serde_json::to_value(&params)?;
+        debug!("FakeBot 收到调用: {} {}", action, params);
+
+        let result = match self.responders.get(action) {
+            Some(responder) => responder(&params),
+            None => Ok(Value::Null),
+        };
+        let response = match result {
+            Ok(data) => json!({
+                "status": "ok",
+                "retcode": 0,
+                "data": data,
+                "echo": echo,
+            }),
+            Err((retcode, message)) => json!({
+                "status": "failed",
+                "retcode": retcode,
+                "message": message,
+                "data": null,
+                "echo": echo,
+            }),
+        };
+
+        self.calls.lock().unwrap().push(ApiCall { action, params });
+        self.called.notify_waiters();
+
+        let pending = api::ApiResponsePending::new(echo);
+        echo_send_result(&echo.to_string(), Utf8Bytes::from(response.to_string()));
+
+        Ok(pending)
+    }
+
+    fn connection(&self) -> watch::Receiver<bool> {
+        self.connected.subscribe()
+    }
+}
+
+#[async_trait]
+impl BotHandler for FakeBot {
+    async fn init(
+        &self,
+        _event: mpsc::UnboundedSender<String>,
+        _api: mpsc::UnboundedSender<String>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    async fn handle_api(&self, _message: Utf8Bytes) {}
+
+    async fn handle_event(&self, event: Utf8Bytes) {
+        match network::parse_event(&event) {
+            Ok(evt) => self.inject(evt),
+            Err(e) => error!("解析注入的事件失败: {:?}", e),
+        }
+    }
+
+    async fn on_connect(&self) {}
+
+    async fn on_disconnect(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{Context, message::event_message::Message};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[tokio::test]
+    async fn test_echo_command() -> Result<()> {
+        let bot = FakeBot::spawn_router();
+        bot.group_message(123, 456, "/echo 你好")?;
+
+        let call = bot
+            .wait_for("send_group_forward_msg", TIMEOUT)
+            .await
+            .expect("没有收到转发消息");
+        assert_eq!(call.params["group_id"], 123);
+        assert!(call.params.to_string().contains("你说的是: /echo 你好"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_alias_command() -> Result<()> {
+        let bot = FakeBot::spawn_router();
+        bot.private_message(456, "/复读 你好")?;
+
+        let call = bot
+            .wait_for("send_private_forward_msg", TIMEOUT)
+            .await
+            .expect("别名没有触发指令");
+        assert!(call.params.to_string().contains("你说的是: /复读 你好"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_help_in_private() -> Result<()> {
+        let bot = FakeBot::spawn_router();
+        bot.private_message(456, "/help")?;
+
+        let call = bot
+            .wait_for("send_private_forward_msg", TIMEOUT)
+            .await
+            .expect("没有收到帮助信息");
+        assert_eq!(call.params["user_id"], 456);
+        assert!(call.params.to_string().contains("指令: help"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scripted_failure() -> Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        bot.script("set_group_special_title", |_| Err("权限不足".to_string()));
+        bot.group_message(123, 456, "hello")?;
+
+        let Some(Event::Message(msg)) = subscribe.recv().await else {
+            panic!("没有收到注入的消息");
+        };
+        let ctx = Context::<FakeBot, Message>::new(bot.clone(), Arc::new(*msg));
+        let err = ctx.set_title("测试".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("权限不足"));
+
+        let calls = bot.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].params["special_title"], "测试");
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_connection() {
+        let (bot, _subscribe) = FakeBot::new();
+        assert!(*bot.connection().borrow());
+        // 不修改传输层维护的全局状态
+        assert!(!*network::subscribe_connected().borrow());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_shape() -> Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        let before = network::unknown_shape_count();
+        let event = json!({
+            "post_type": "notice",
+            "notice_type": "notify",
+            "sub_type": "input_status",
+            "time": 1700000000,
+            "self_id": FAKE_SELF_ID,
+            "user_id": 456,
+        });
+        bot.handle_event(Utf8Bytes::from(event.to_string())).await;
+
+        assert!(subscribe.recv().await.is_some());
+        // 其他测试可能同时注入未知结构，这里只检查计数增加
+        assert!(network::unknown_shape_count() > before);
+
+        Ok(())
+    }
+}
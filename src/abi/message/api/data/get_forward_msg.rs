@@ -1,8 +1,10 @@
 use crate::abi::message::{
     MessageReceive,
     api::{ApiResponse, Data},
+    message_body::SegmentReceive,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetForwardMsgData {
@@ -13,3 +15,85 @@ pub struct GetForwardMsgData {
 impl Data for GetForwardMsgData {}
 
 pub type GetForwardMsgResponse = ApiResponse<GetForwardMsgData>;
+
+/// 合并转发中的一条消息
+#[derive(Debug, Clone)]
+pub struct ForwardNode {
+    pub user_id: Option<i64>,
+    pub nickname: Option<String>,
+    pub message: MessageReceive,
+}
+
+impl ForwardNode {
+    /// Napcat 返回内容放在 `content` 中的消息对象，也兼容 `message` 字段与 node 消息段
+    fn from_value(value: &Value) -> Option<Self> {
+        let (sender, content) = match value["type"].as_str() {
+            Some("node") => (&value["data"], &value["data"]["content"]),
+            _ if !value["content"].is_null() => (&value["sender"], &value["content"]),
+            _ => (&value["sender"], &value["message"]),
+        };
+        let message = serde_json::from_value(content.clone()).ok()?;
+        let user_id = match &sender["user_id"] {
+            Value::String(s) => s.parse().ok(),
+            other => other.as_i64(),
+        };
+        Some(ForwardNode {
+            user_id,
+            nickname: sender["nickname"].as_str().map(str::to_string),
+            message,
+        })
+    }
+}
+
+impl GetForwardMsgData {
+    /// 取出各条消息，无法识别的结构会被跳过
+    pub fn nodes(&self) -> Vec<ForwardNode> {
+        let segments = match &self.messages {
+            MessageReceive::Array(segments) => segments.iter().collect::<Vec<_>>(),
+            MessageReceive::Single(segment) => vec![segment],
+        };
+        segments
+            .into_iter()
+            .filter_map(|segment| match segment {
+                SegmentReceive::Unknown(value) => ForwardNode::from_value(value),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nodes() {
+        let data = json!({
+            "messages": [
+                {
+                    "message_id": 1,
+                    "sender": { "user_id": 123, "nickname": "甲" },
+                    "content": [{ "type": "text", "data": { "text": "第一条" } }],
+                },
+                {
+                    "type": "node",
+                    "data": {
+                        "user_id": "456",
+                        "nickname": "乙",
+                        "content": [{ "type": "text", "data": { "text": "第二条" } }],
+                    },
+                },
+                { "message_id": 3 },
+            ],
+        });
+        let data = serde_json::from_value::<GetForwardMsgData>(data).unwrap();
+        let nodes = data.nodes();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].user_id, Some(123));
+        assert_eq!(nodes[0].message.get_text(), "第一条");
+        assert_eq!(nodes[1].nickname.as_deref(), Some("乙"));
+        assert_eq!(nodes[1].user_id, Some(456));
+        assert_eq!(nodes[1].message.get_text(), "第二条");
+    }
+}
@@ -0,0 +1,15 @@
+use crate::abi::message::api::{ApiResponse, Data};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetImageData {
+    /// 图片在 Napcat 所在机器上的本地路径
+    pub file: String,
+    pub url: Option<String>,
+    pub file_size: Option<String>,
+    pub file_name: Option<String>,
+}
+
+impl Data for GetImageData {}
+
+pub type GetImageResponse = ApiResponse<GetImageData>;
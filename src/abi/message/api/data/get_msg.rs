@@ -0,0 +1,9 @@
+use crate::abi::message::{
+    api::{ApiResponse, Data},
+    event_message::Message,
+};
+
+/// 与收到的消息事件结构相同，可用于解析 `Reply` 消息段引用的消息
+impl Data for Message {}
+
+pub type GetMsgResponse = ApiResponse<Message>;
@@ -0,0 +1,60 @@
+use crate::abi::message::api::{ApiResponse, Data};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GroupFile {
+    pub group_id: i64,
+    pub file_id: String,
+    pub file_name: String,
+    pub busid: i32,
+    pub file_size: i64,
+    pub upload_time: i64,
+    pub dead_time: i64,
+    pub modify_time: i64,
+    pub download_times: i32,
+    pub uploader: i64,
+    pub uploader_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GroupFolder {
+    pub group_id: i64,
+    pub folder_id: String,
+    pub folder_name: String,
+    pub create_time: i64,
+    pub creator: i64,
+    pub creator_name: String,
+    pub total_file_count: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GroupFilesData {
+    #[serde(default)]
+    pub files: Vec<GroupFile>,
+    #[serde(default)]
+    pub folders: Vec<GroupFolder>,
+}
+
+impl Data for GroupFilesData {}
+
+pub type GroupFilesResponse = ApiResponse<GroupFilesData>;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GroupFileUrlData {
+    pub url: String,
+}
+
+impl Data for GroupFileUrlData {}
+
+pub type GroupFileUrlResponse = ApiResponse<GroupFileUrlData>;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UploadGroupFileData {
+    /// 部分版本的 Napcat 不返回文件 ID
+    #[serde(default)]
+    pub file_id: Option<String>,
+}
+
+impl Data for UploadGroupFileData {}
+
+pub type UploadGroupFileResponse = ApiResponse<UploadGroupFileData>;
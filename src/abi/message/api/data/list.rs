@@ -0,0 +1,21 @@
+use crate::abi::message::api::{ApiResponse, Data, GetGroupInfoData, GroupMemberInfoData};
+use serde::{Deserialize, Serialize};
+
+impl Data for Vec<GroupMemberInfoData> {}
+
+pub type GroupMemberListResponse = ApiResponse<Vec<GroupMemberInfoData>>;
+
+impl Data for Vec<GetGroupInfoData> {}
+
+pub type GroupListResponse = ApiResponse<Vec<GetGroupInfoData>>;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FriendData {
+    pub user_id: i64,
+    pub nickname: String,
+    pub remark: String,
+}
+
+impl Data for Vec<FriendData> {}
+
+pub type FriendListResponse = ApiResponse<Vec<FriendData>>;
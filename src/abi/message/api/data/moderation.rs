@@ -0,0 +1,10 @@
+use crate::abi::message::api::{ApiResponse, Data};
+use serde::{Deserialize, Serialize};
+
+/// 群管理类动作成功时不返回数据
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModerationData {}
+
+impl Data for ModerationData {}
+
+pub type ModerationResponse = ApiResponse<ModerationData>;
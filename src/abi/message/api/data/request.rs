@@ -0,0 +1,9 @@
+use crate::abi::message::api::{ApiResponse, Data};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SetRequestData {}
+
+impl Data for SetRequestData {}
+
+pub type SetRequestResponse = ApiResponse<SetRequestData>;
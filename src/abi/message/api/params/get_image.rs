@@ -0,0 +1,16 @@
+use super::Params;
+use crate::abi::message::api::data;
+use helper::api;
+use serde::{Deserialize, Serialize};
+
+#[api("/get_image", data::GetImageResponse)]
+pub struct GetImage {
+    /// 图片消息段中的 file 字段
+    file: String,
+}
+
+impl GetImage {
+    pub fn new(file: String) -> Self {
+        Self { file }
+    }
+}
@@ -0,0 +1,27 @@
+use super::Params;
+use crate::abi::message::api::data;
+use helper::api;
+use serde::{Deserialize, Serialize};
+
+#[api("/get_msg", data::GetMsgResponse)]
+pub struct GetMsg {
+    message_id: i64,
+}
+
+impl GetMsg {
+    pub const fn new(message_id: i64) -> Self {
+        Self { message_id }
+    }
+}
+
+/// 获取合并转发的内容，`message_id` 为 `Forward` 消息段中的 id
+#[api("/get_forward_msg", data::GetForwardMsgResponse)]
+pub struct GetForwardMsg {
+    message_id: String,
+}
+
+impl GetForwardMsg {
+    pub fn new(message_id: String) -> Self {
+        Self { message_id }
+    }
+}
@@ -0,0 +1,69 @@
+use super::Params;
+use crate::abi::message::api::data;
+use helper::api;
+use serde::{Deserialize, Serialize};
+
+#[api("/get_group_root_files", data::GroupFilesResponse)]
+pub struct GroupRootFiles {
+    group_id: i64,
+}
+
+impl GroupRootFiles {
+    pub const fn new(group_id: i64) -> Self {
+        Self { group_id }
+    }
+}
+
+#[api("/get_group_files_by_folder", data::GroupFilesResponse)]
+pub struct GroupFilesByFolder {
+    group_id: i64,
+    folder_id: String,
+}
+
+impl GroupFilesByFolder {
+    pub fn new(group_id: i64, folder_id: String) -> Self {
+        Self {
+            group_id,
+            folder_id,
+        }
+    }
+}
+
+#[api("/get_group_file_url", data::GroupFileUrlResponse)]
+pub struct GroupFileUrl {
+    group_id: i64,
+    file_id: String,
+    busid: i32,
+}
+
+impl GroupFileUrl {
+    pub fn new(group_id: i64, file_id: String, busid: i32) -> Self {
+        Self {
+            group_id,
+            file_id,
+            busid,
+        }
+    }
+}
+
+#[api("/upload_group_file", data::UploadGroupFileResponse)]
+pub struct UploadGroupFile {
+    group_id: i64,
+    /// 本地路径、网络地址或 base64:// 开头的文件内容
+    file: String,
+    name: String,
+    /// 目标文件夹 ID，不填写时上传到根目录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+}
+
+impl UploadGroupFile {
+    pub fn new(group_id: i64, file: String, name: String, folder: Option<String>) -> Self {
+        Self {
+            group_id,
+            file,
+            name,
+            folder,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use super::Params;
+use crate::abi::message::api::data;
+use helper::api;
+use serde::{Deserialize, Serialize};
+
+#[api("/get_group_member_list", data::GroupMemberListResponse)]
+pub struct GroupMemberList {
+    group_id: i64,
+    no_cache: bool,
+}
+
+impl GroupMemberList {
+    pub const fn new(group_id: i64, no_cache: bool) -> Self {
+        Self { group_id, no_cache }
+    }
+}
+
+#[api("/get_group_list", data::GroupListResponse)]
+pub struct GroupList {
+    no_cache: bool,
+}
+
+impl GroupList {
+    pub const fn new(no_cache: bool) -> Self {
+        Self { no_cache }
+    }
+}
+
+#[api("/get_friend_list", data::FriendListResponse)]
+pub struct FriendList {
+    no_cache: bool,
+}
+
+impl FriendList {
+    pub const fn new(no_cache: bool) -> Self {
+        Self { no_cache }
+    }
+}
@@ -1,12 +1,24 @@
 mod get_group_info;
+mod get_image;
+mod get_msg;
+mod group_file;
 mod group_member_info;
+mod list;
+mod moderation;
 mod poke;
+mod request;
 mod send_msg;
 mod title;
 
 pub use get_group_info::*;
+pub use get_image::*;
+pub use get_msg::*;
+pub use group_file::*;
 pub use group_member_info::*;
+pub use list::*;
+pub use moderation::*;
 pub use poke::*;
+pub use request::*;
 pub use send_msg::*;
 pub use title::*;
 
@@ -0,0 +1,120 @@
+use super::Params;
+use crate::abi::message::api::data;
+use helper::api;
+use serde::{Deserialize, Serialize};
+
+#[api("/delete_msg", data::ModerationResponse)]
+pub struct DeleteMsg {
+    message_id: i64,
+}
+
+impl DeleteMsg {
+    pub const fn new(message_id: i64) -> Self {
+        Self { message_id }
+    }
+}
+
+#[api("/set_group_ban", data::ModerationResponse)]
+pub struct SetGroupBan {
+    group_id: i64,
+    user_id: i64,
+    /// 禁言时长（秒），为 0 时解除禁言
+    duration: u64,
+}
+
+impl SetGroupBan {
+    pub const fn new(group_id: i64, user_id: i64, duration: u64) -> Self {
+        Self {
+            group_id,
+            user_id,
+            duration,
+        }
+    }
+}
+
+#[api("/set_group_whole_ban", data::ModerationResponse)]
+pub struct SetGroupWholeBan {
+    group_id: i64,
+    enable: bool,
+}
+
+impl SetGroupWholeBan {
+    pub const fn new(group_id: i64, enable: bool) -> Self {
+        Self { group_id, enable }
+    }
+}
+
+#[api("/set_group_kick", data::ModerationResponse)]
+pub struct SetGroupKick {
+    group_id: i64,
+    user_id: i64,
+    /// 拒绝此人之后的加群申请
+    reject_add_request: bool,
+}
+
+impl SetGroupKick {
+    pub const fn new(group_id: i64, user_id: i64, reject_add_request: bool) -> Self {
+        Self {
+            group_id,
+            user_id,
+            reject_add_request,
+        }
+    }
+}
+
+#[api("/set_group_card", data::ModerationResponse)]
+pub struct SetGroupCard {
+    group_id: i64,
+    user_id: i64,
+    /// 为空时删除群名片
+    card: String,
+}
+
+impl SetGroupCard {
+    pub fn new(group_id: i64, user_id: i64, card: String) -> Self {
+        Self {
+            group_id,
+            user_id,
+            card,
+        }
+    }
+}
+
+#[api("/set_essence_msg", data::ModerationResponse)]
+pub struct SetEssenceMsg {
+    message_id: i64,
+}
+
+impl SetEssenceMsg {
+    pub const fn new(message_id: i64) -> Self {
+        Self { message_id }
+    }
+}
+
+#[api("/delete_essence_msg", data::ModerationResponse)]
+pub struct DeleteEssenceMsg {
+    message_id: i64,
+}
+
+impl DeleteEssenceMsg {
+    pub const fn new(message_id: i64) -> Self {
+        Self { message_id }
+    }
+}
+
+#[api("/set_group_admin", data::ModerationResponse)]
+pub struct SetGroupAdmin {
+    group_id: i64,
+    user_id: i64,
+    enable: bool,
+}
+
+impl SetGroupAdmin {
+    pub const fn new(group_id: i64, user_id: i64, enable: bool) -> Self {
+        Self {
+            group_id,
+            user_id,
+            enable,
+        }
+    }
+}
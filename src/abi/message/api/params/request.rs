@@ -0,0 +1,41 @@
+use super::Params;
+use crate::abi::message::api::data;
+use helper::api;
+use serde::{Deserialize, Serialize};
+
+#[api("/set_friend_add_request", data::SetRequestResponse)]
+pub struct SetFriendAddRequest {
+    flag: String,
+    approve: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remark: Option<String>,
+}
+
+impl SetFriendAddRequest {
+    pub fn new(flag: String, approve: bool) -> Self {
+        Self {
+            flag,
+            approve,
+            remark: None,
+        }
+    }
+}
+
+#[api("/set_group_add_request", data::SetRequestResponse)]
+pub struct SetGroupAddRequest {
+    flag: String,
+    approve: bool,
+    /// 拒绝理由，仅在拒绝时有效
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+impl SetGroupAddRequest {
+    pub fn new(flag: String, approve: bool, reason: Option<String>) -> Self {
+        Self {
+            flag,
+            approve,
+            reason,
+        }
+    }
+}
@@ -107,6 +107,10 @@ fn get_msg(
     target: Target,
 ) -> Vec<SegmentSend> {
     let mut message = Vec::with_capacity(message_list.len() + 3);
+    let self_id = match &*msg {
+        event_body::message::Message::Private(p) => p.self_id,
+        event_body::message::Message::Group(g) => g.self_id,
+    };
     if is_echo {
         let msg_content = match &*msg {
             event_body::message::Message::Private(p) => &p.message,
@@ -148,7 +152,7 @@ fn get_msg(
     for msg in messages {
         message.push(message_body::SegmentSend::Node(
             message_body::node::DataSend::Content(message_body::node::DataSend2 {
-                user_id: "1363408373".to_string(),
+                user_id: self_id.to_string(),
                 nickname: "指令回复".to_string(),
                 content: box_new!(MessageSend, msg.clone()),
             }),
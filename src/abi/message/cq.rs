@@ -0,0 +1,282 @@
+//! CQ 码与消息段的相互转换
+//!
+//! 格式为 `[CQ:类型,键=值,...]`，文本中的 `&`、`[`、`]` 以及参数值中的 `,`
+//! 需要转义为 `&amp;`、`&#91;`、`&#93;`、`&#44;`。消息段经过 serde 转换，
+//! 因此所有能以 JSON 收发的消息段都能以 CQ 码表示。
+
+use super::message_body::{MessageReceive, MessageSend, SegmentReceive, SegmentSend};
+use anyhow::{Result, anyhow};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// 转义文本中的特殊字符
+pub fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('[', "&#91;")
+        .replace(']', "&#93;")
+}
+
+/// 转义参数值，比文本多转义逗号
+pub fn escape_param(value: &str) -> String {
+    escape_text(value).replace(',', "&#44;")
+}
+
+pub fn unescape(text: &str) -> String {
+    text.replace("&#91;", "[")
+        .replace("&#93;", "]")
+        .replace("&#44;", ",")
+        .replace("&amp;", "&")
+}
+
+fn text_value(text: &str) -> Value {
+    serde_json::json!({ "type": "text", "data": { "text": unescape(text) } })
+}
+
+/// 把一个 CQ 码的内容（不含首尾的 `[CQ:` 与 `]`）转换为 `{"type": .., "data": {..}}`
+fn code_value(body: &str) -> Value {
+    let mut parts = body.split(',');
+    let kind = parts.next().unwrap_or_default();
+    let mut data = Map::new();
+    for part in parts {
+        // 没有值的参数直接忽略
+        if let Some((key, value)) = part.split_once('=') {
+            data.insert(key.to_string(), Value::String(unescape(value)));
+        }
+    }
+    // 转发节点的内容本身也是 CQ 码
+    if kind == "node"
+        && let Some(Value::String(content)) = data.get("content")
+    {
+        let content = Value::Array(parse_values(content));
+        data.insert("content".to_string(), content);
+    }
+    serde_json::json!({ "type": kind, "data": data })
+}
+
+/// 解析为 JSON 形式的消息段，未闭合的 `[CQ:` 按普通文本处理
+fn parse_values(text: &str) -> Vec<Value> {
+    let mut values = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let Some(start) = rest.find("[CQ:") else {
+            values.push(text_value(rest));
+            break;
+        };
+        let Some(len) = rest[start..].find(']') else {
+            values.push(text_value(rest));
+            break;
+        };
+        if start > 0 {
+            values.push(text_value(&rest[..start]));
+        }
+        values.push(code_value(&rest[start + 4..start + len]));
+        rest = &rest[start + len + 1..];
+    }
+    values
+}
+
+/// 把参数中形如数字的值转为数字
+fn with_numbers(mut value: Value) -> Value {
+    if let Some(Value::Object(data)) = value.get_mut("data") {
+        for value in data.values_mut() {
+            if let Value::String(s) = value
+                && let Ok(number) = s.parse::<i64>()
+            {
+                *value = Value::from(number);
+            }
+        }
+    }
+    value
+}
+
+/// 数字类型的字段在 CQ 码中同样是字符串，直接反序列化失败时把形如数字的值转为数字再试一次
+fn from_value<S: DeserializeOwned>(value: Value) -> serde_json::Result<S> {
+    serde_json::from_value(value.clone())
+        .or_else(|e| serde_json::from_value(with_numbers(value)).map_err(|_| e))
+}
+
+/// 解析 CQ 码字符串中的所有消息段
+pub fn parse<S: DeserializeOwned>(text: &str) -> Result<Vec<S>> {
+    parse_values(text)
+        .into_iter()
+        .map(|value| {
+            let kind = value["type"].as_str().unwrap_or_default().to_string();
+            from_value(value).map_err(|e| anyhow!("无法解析 CQ 码 {}: {}", kind, e))
+        })
+        .collect()
+}
+
+fn render_param(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(escape_param(s)),
+        // 嵌套的转发消息内容同样渲染为 CQ 码
+        Value::Array(segments) => Some(escape_param(&render_values(segments))),
+        other => Some(escape_param(&other.to_string())),
+    }
+}
+
+fn render_values(segments: &[Value]) -> String {
+    let mut text = String::new();
+    for segment in segments {
+        let kind = segment["type"].as_str().unwrap_or_default();
+        if kind == "text" {
+            text.push_str(&escape_text(
+                segment["data"]["text"].as_str().unwrap_or_default(),
+            ));
+            continue;
+        }
+        text.push_str("[CQ:");
+        text.push_str(kind);
+        if let Some(data) = segment["data"].as_object() {
+            for (key, value) in data {
+                if let Some(value) = render_param(value) {
+                    text.push(',');
+                    text.push_str(key);
+                    text.push('=');
+                    text.push_str(&value);
+                }
+            }
+        }
+        text.push(']');
+    }
+    text
+}
+
+/// 把消息段渲染为 CQ 码字符串
+pub fn render<S: Serialize>(segments: &[S]) -> String {
+    let segments = segments
+        .iter()
+        .filter_map(|segment| serde_json::to_value(segment).ok())
+        .collect::<Vec<_>>();
+    render_values(&segments)
+}
+
+impl MessageReceive {
+    /// 解析 CQ 码字符串，无法识别的消息段保留为 `SegmentReceive::Unknown`
+    pub fn from_cq(text: &str) -> Self {
+        let segments = parse_values(text)
+            .into_iter()
+            .map(|value| {
+                // Unknown 可以接收任意 JSON，需要单独判断是否应当转为数字再试
+                match serde_json::from_value(value.clone()) {
+                    Ok(SegmentReceive::Unknown(_)) | Err(_) => {
+                        match serde_json::from_value(with_numbers(value.clone())) {
+                            Ok(SegmentReceive::Unknown(_)) | Err(_) => {
+                                SegmentReceive::Unknown(Box::new(value))
+                            }
+                            Ok(segment) => segment,
+                        }
+                    }
+                    Ok(segment) => segment,
+                }
+            })
+            .collect();
+        MessageReceive::Array(segments)
+    }
+
+    pub fn to_cq(&self) -> String {
+        match self {
+            MessageReceive::Array(segments) => render(segments),
+            MessageReceive::Single(segment) => render(std::slice::from_ref(segment)),
+        }
+    }
+}
+
+impl MessageSend {
+    pub fn from_cq(text: &str) -> Result<Self> {
+        Ok(MessageSend::Array(parse::<SegmentSend>(text)?))
+    }
+
+    pub fn to_cq(&self) -> String {
+        match self {
+            MessageSend::Array(segments) => render(segments),
+            MessageSend::Single(segment) => render(std::slice::from_ref(segment)),
+        }
+    }
+}
+
+impl fmt::Display for MessageReceive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_cq())
+    }
+}
+
+impl fmt::Display for MessageSend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_cq())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::message::from_str;
+
+    #[test]
+    fn test_escape() {
+        let text = "a&b[c],d";
+        assert_eq!(escape_text(text), "a&amp;b&#91;c&#93;,d");
+        assert_eq!(escape_param(text), "a&amp;b&#91;c&#93;&#44;d");
+        assert_eq!(unescape(&escape_param(text)), text);
+        // 先还原实体再还原 &，避免 `&amp;#91;` 被还原成 `[`
+        assert_eq!(unescape("&amp;#91;"), "&#91;");
+    }
+
+    #[test]
+    fn test_parse_receive() {
+        let message = MessageReceive::from_cq(
+            "[CQ:reply,id=42][CQ:at,qq=123] 你好&#91;吗&#93;[CQ:image,file=a.jpg,url=http://x/a?b=1&amp;c=2][CQ:mface,id=7]",
+        );
+        let MessageReceive::Array(segments) = &message else {
+            unreachable!()
+        };
+        assert_eq!(segments.len(), 5);
+        assert!(matches!(&segments[0], SegmentReceive::Reply(r) if r.id == "42"));
+        assert!(matches!(&segments[1], SegmentReceive::At(at) if at.qq == "123"));
+        assert_eq!(message.get_text(), " 你好[吗]");
+        assert!(
+            matches!(&segments[3], SegmentReceive::Image(image) if image.url == "http://x/a?b=1&c=2")
+        );
+        assert_eq!(message.count_unknown(), 1);
+
+        let record = MessageReceive::from_cq("[CQ:record,file=a.amr,magic=1,url=http://x/a.amr]");
+        assert!(matches!(
+            &record,
+            MessageReceive::Array(segments) if matches!(&segments[0], SegmentReceive::Record(r) if *r.magic == 1)
+        ));
+
+        // 未闭合的 CQ 码按文本处理
+        assert_eq!(
+            MessageReceive::from_cq("[CQ:at,qq=1").get_text(),
+            "[CQ:at,qq=1"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let message = MessageSend::new_message()
+            .reply("42")
+            .at("123")
+            .text("a,b&[c]")
+            .image_url("http://x/a.png?b=1,2")
+            .face("14")
+            .build();
+        let text = message.to_cq();
+        assert_eq!(
+            text,
+            "[CQ:reply,id=42][CQ:at,qq=123] a,b&amp;&#91;c&#93;[CQ:image,cache=1,file=http://x/a.png?b=1&#44;2,proxy=1][CQ:face,id=14]"
+        );
+        assert_eq!(MessageSend::from_cq(&text)?.to_cq(), text);
+
+        let node = MessageSend::new_message()
+            .node_content("10000", "Bot", from_str("[嵌套]"))
+            .build();
+        assert_eq!(MessageSend::from_cq(&node.to_cq())?.to_cq(), node.to_cq());
+
+        assert!(MessageSend::from_cq("[CQ:unknown,a=1]").is_err());
+
+        Ok(())
+    }
+}
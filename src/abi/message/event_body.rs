@@ -1,7 +1,7 @@
 use crate::abi::message::Sender;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Target {
     Group(i64),
     Private(i64),
@@ -21,6 +21,24 @@ pub trait MessageType {
     fn get_sender(&self) -> Sender;
 }
 
+/// 从未知结构中尽量取出会话目标，两者都没有时退化为 `Private(0)`
+fn unknown_target(value: &serde_json::Value) -> Target {
+    match (value["group_id"].as_i64(), value["user_id"].as_i64()) {
+        (Some(group_id), _) => Target::Group(group_id),
+        (None, Some(user_id)) => Target::Private(user_id),
+        (None, None) => Target::Private(0),
+    }
+}
+
+fn unknown_sender(value: &serde_json::Value) -> Sender {
+    Sender {
+        nickname: None,
+        user_id: value["user_id"].as_i64(),
+        card: None,
+        role: None,
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(tag = "post_type", rename_all = "snake_case")]
 pub enum Event {
@@ -31,6 +49,27 @@ pub enum Event {
     MessageSent(Box<message_sent::MessageSent>),
 }
 
+impl Event {
+    /// 事件中落入 `Unknown` 的结构数量，包括消息里的未知消息段
+    pub fn count_unknown(&self) -> usize {
+        match self {
+            Event::Message(msg) => match msg.as_ref() {
+                message::Message::Private(p) => p.message.count_unknown(),
+                message::Message::Group(p) => p.message.count_unknown(),
+            },
+            Event::MessageSent(msg) => match msg.as_ref() {
+                message_sent::MessageSent::Private(p) => p.message.count_unknown(),
+                message_sent::MessageSent::Group(p) => p.message.count_unknown(),
+            },
+            Event::Notice(notice::Notice::Unknown(_))
+            | Event::Notice(notice::Notice::Notify(notice::Notify::Unknown(_)))
+            | Event::Request(request::Request::Unknown(_))
+            | Event::MetaEvent(meta::MetaEvent::Unknown(_)) => 1,
+            _ => 0,
+        }
+    }
+}
+
 pub mod message {
     use crate::abi::message::{
         MessageReceive, SenderGroup, SenderPrivate,
@@ -39,7 +78,7 @@ pub mod message {
 
     use super::*;
 
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     #[serde(tag = "message_type", rename_all = "snake_case")]
     pub enum Message {
         Private(Private),
@@ -160,6 +199,9 @@ pub mod notice {
         FriendRecall(FriendRecall),
         GroupMsgEmojiLike(GroupMsgEmojiLike),
         Notify(Notify),
+        /// 未知或无法解析的通知，保留原始 JSON
+        #[serde(untagged)]
+        Unknown(serde_json::Value),
     }
 
     impl MessageType for Notice {
@@ -178,8 +220,10 @@ pub mod notice {
                     Notify::LuckyKing(lucky_king) => Target::Group(lucky_king.group_id),
                     Notify::Honor(honor) => Target::Group(honor.group_id),
                     Notify::Title(title) => Target::Group(title.group_id),
+                    Notify::Unknown(value) => unknown_target(value),
                 },
                 Notice::GroupMsgEmojiLike(g) => Target::Group(g.group_id),
+                Notice::Unknown(value) => unknown_target(value),
             }
         }
 
@@ -266,6 +310,7 @@ pub mod notice {
                         card: None,
                         role: None,
                     },
+                    Notify::Unknown(value) => unknown_sender(value),
                 },
                 Notice::GroupMsgEmojiLike(g) => Sender {
                     nickname: None,
@@ -273,6 +318,7 @@ pub mod notice {
                     card: None,
                     role: None,
                 },
+                Notice::Unknown(value) => unknown_sender(value),
             }
         }
     }
@@ -404,6 +450,8 @@ pub mod notice {
         LuckyKing(notify::LuckyKing),
         Honor(notify::Honor),
         Title(notify::Title),
+        #[serde(untagged)]
+        Unknown(serde_json::Value),
     }
 
     mod notify {
@@ -463,6 +511,8 @@ pub mod request {
     pub enum Request {
         Friend(Friend),
         Group(Group),
+        #[serde(untagged)]
+        Unknown(serde_json::Value),
     }
 
     impl MessageType for Request {
@@ -470,6 +520,7 @@ pub mod request {
             match self {
                 Request::Friend(n) => Target::Private(n.user_id),
                 Request::Group(n) => Target::Group(n.group_id),
+                Request::Unknown(value) => unknown_target(value),
             }
         }
 
@@ -495,6 +546,7 @@ pub mod request {
                     card: None,
                     role: None,
                 },
+                Request::Unknown(value) => unknown_sender(value),
             }
         }
     }
@@ -535,6 +587,8 @@ pub mod meta {
     pub enum MetaEvent {
         Lifecycle(Lifecycle),
         Heartbeat(Heartbeat),
+        #[serde(untagged)]
+        Unknown(serde_json::Value),
     }
 
     #[derive(Serialize, Deserialize, Debug)]
@@ -572,13 +626,37 @@ pub mod message_sent {
 
     use super::*;
 
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     #[serde(tag = "message_type", rename_all = "snake_case")]
     pub enum MessageSent {
         Group(Group),
         Private(Private),
     }
 
+    impl MessageSent {
+        pub fn message_id(&self) -> i64 {
+            match self {
+                MessageSent::Group(g) => g.message_id,
+                MessageSent::Private(p) => p.message_id,
+            }
+        }
+
+        /// 消息发往的会话，私聊时 `user_id` 是 Bot 自己，对方在 `target_id` 中
+        pub fn get_target(&self) -> Target {
+            match self {
+                MessageSent::Group(g) => Target::Group(g.group_id.unwrap_or_default()),
+                MessageSent::Private(p) => Target::Private(p.target_id.unwrap_or(p.user_id)),
+            }
+        }
+
+        pub fn get_message(&self) -> &MessageReceive {
+            match self {
+                MessageSent::Group(g) => &g.message,
+                MessageSent::Private(p) => &p.message,
+            }
+        }
+    }
+
     #[derive(Serialize, Deserialize, Debug)]
     #[serde(rename_all = "snake_case")]
     pub enum SubType {
@@ -594,7 +672,7 @@ pub mod message_sent {
         String,
     }
 
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     pub struct Group {
         pub real_seq: Option<i64>,
         pub temp_source: Option<i64>,
@@ -616,7 +694,7 @@ pub mod message_sent {
         pub font: i64,
     }
 
-    #[derive(Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug)]
     pub struct Private {
         pub real_seq: Option<i64>,
         pub temp_source: Option<i64>,
@@ -1,10 +1,5 @@
 use helper::define_default_type;
 use serde::{Deserialize, Deserializer, Serialize, de};
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct CQ {
-    pub user_id: u64,
-    pub message: String,
-}
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type", content = "data", rename_all = "snake_case")]
@@ -26,6 +21,9 @@ pub enum SegmentReceive {
     Xml(xml::DataReceive),
     Json(json::DataReceive),
     File(Box<file::DataReceive>),
+    /// 未知类型的消息段（如 markdown、mface），保留原始 JSON
+    #[serde(untagged)]
+    Unknown(Box<serde_json::Value>),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -60,7 +58,6 @@ type ArrayReceive = Vec<SegmentReceive>;
 pub enum MessageReceive {
     Array(ArrayReceive),
     Single(SegmentReceive),
-    //Cq(CQ),
 }
 
 impl<'de> de::Deserialize<'de> for MessageReceive {
@@ -74,7 +71,15 @@ impl<'de> de::Deserialize<'de> for MessageReceive {
             type Value = MessageReceive;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("array or map")
+                formatter.write_str("array, map or CQ string")
+            }
+
+            // message_format 为 string 时消息是 CQ 码字符串
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(MessageReceive::from_cq(v))
             }
 
             // 当探测到 JSON 以 '[' 开头时
@@ -128,6 +133,17 @@ impl MessageReceive {
             _ => String::new(),
         }
     }
+
+    /// 未能识别的消息段数量
+    pub fn count_unknown(&self) -> usize {
+        match self {
+            MessageReceive::Single(seg) => matches!(seg, SegmentReceive::Unknown(_)) as usize,
+            MessageReceive::Array(arr) => arr
+                .iter()
+                .filter(|seg| matches!(seg, SegmentReceive::Unknown(_)))
+                .count(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -244,6 +260,82 @@ mod tests {
         }
         println!("========================================\n");
     }
+
+    #[test]
+    fn test_unknown_fallback() {
+        use crate::abi::message::Event;
+        use serde_json::json;
+
+        let message = json!({
+            "post_type": "message",
+            "message_type": "private",
+            "time": 1700000000,
+            "self_id": 10000,
+            "sub_type": "friend",
+            "message_id": 1,
+            "user_id": 456,
+            "raw_message": "[mface]",
+            "font": 0,
+            "sender": { "user_id": 456, "nickname": "TestUser" },
+            "message": [
+                { "type": "text", "data": { "text": "看" } },
+                { "type": "mface", "data": { "emoji_id": "1", "summary": "[动画表情]" } },
+            ],
+        });
+        let event = serde_json::from_value::<Event>(message).unwrap();
+        assert_eq!(event.count_unknown(), 1);
+
+        let notify = json!({
+            "post_type": "notice",
+            "notice_type": "notify",
+            "sub_type": "input_status",
+            "time": 1700000000,
+            "self_id": 10000,
+            "user_id": 456,
+        });
+        let event = serde_json::from_value::<Event>(notify).unwrap();
+        assert_eq!(event.count_unknown(), 1);
+
+        let notice = json!({
+            "post_type": "notice",
+            "notice_type": "bot_offline",
+            "time": 1700000000,
+            "self_id": 10000,
+        });
+        let event = serde_json::from_value::<Event>(notice).unwrap();
+        assert_eq!(event.count_unknown(), 1);
+    }
+
+    #[test]
+    fn test_message_sent_target() {
+        use crate::abi::message::{Event, Target};
+        use serde_json::json;
+
+        let sent = json!({
+            "post_type": "message_sent",
+            "message_type": "private",
+            "time": 1700000000,
+            "self_id": 10000,
+            "target_id": 456,
+            "sub_type": "friend",
+            "message_id": 7,
+            "message_seq": 7,
+            "real_id": 7,
+            "user_id": 10000,
+            "raw_message": "你好",
+            "font": 14,
+            "message_format": "array",
+            "sender": { "user_id": 10000, "nickname": "Bot" },
+            "message": [{ "type": "text", "data": { "text": "你好" } }],
+        });
+        let Event::MessageSent(sent) = serde_json::from_value::<Event>(sent).unwrap() else {
+            unreachable!()
+        };
+        // 私聊时 user_id 是 Bot 自己，会话对象取 target_id
+        assert_eq!(sent.get_target(), Target::Private(456));
+        assert_eq!(sent.message_id(), 7);
+        assert_eq!(sent.get_message().get_text(), "你好");
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
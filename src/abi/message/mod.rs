@@ -1,4 +1,5 @@
 pub mod api;
+pub mod cq;
 pub mod event_body;
 pub mod file;
 pub mod helper;
@@ -106,6 +107,9 @@ fn receive_seq_to_send_seq(seq: &SegmentReceive) -> SegmentSend {
         SegmentReceive::File(p) => SegmentSend::File(message_body::file::DataSend {
             file: FileUrl::new(p.url.clone()),
         }),
+        SegmentReceive::Unknown(p) => SegmentSend::Text(message_body::text::DataSend {
+            text: format!("[{}]", p["type"].as_str().unwrap_or("未知消息段")),
+        }),
     }
 }
 
@@ -1,28 +1,62 @@
 pub mod echo;
+#[cfg(any(test, feature = "testing"))]
+pub mod fake;
 pub mod message;
 pub mod network;
+pub mod record;
+#[cfg(any(test, feature = "testing"))]
+pub mod replay;
+pub mod reverse;
 pub mod router;
+pub mod scheduler;
 pub mod utils;
+pub mod webhook;
 pub mod websocket;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 pub use router::context::Context;
 pub use router::handler::Handler;
 use router::handler::NapcatRouter;
 
 use crate::{
-    abi::{network::NapcatAdapter, router::handler::Router},
-    config::ServerConfig,
+    abi::{
+        network::{HttpAdapter, NapcatAdapter},
+        router::handler::Router,
+    },
+    config::{ConnectMode, ServerConfig},
 };
 
 pub async fn run(config: ServerConfig) -> Result<NapcatRouter<NapcatAdapter>> {
+    if config.record_frames {
+        record::init(record::RECORD_DIR)?;
+    }
     let (adapter, subscribe) = network::NapcatAdapter::new();
-    let mut client = websocket::BotWebsocketClient::new(config, adapter);
-    client.connect().await?;
-    let router = NapcatRouter::new(subscribe, client);
+    let router = match config.mode {
+        ConnectMode::Forward => {
+            let mut client = websocket::BotWebsocketClient::new(config, adapter);
+            client.connect().await?;
+            NapcatRouter::new(subscribe, Box::new(client))
+        }
+        ConnectMode::Reverse => {
+            let mut server = reverse::BotReverseServer::new(config, adapter);
+            server.listen().await?;
+            NapcatRouter::new(subscribe, Box::new(server))
+        }
+        ConnectMode::Http => bail!("HTTP 模式请使用 run_http 启动"),
+    };
     Ok(router)
 }
 
+pub async fn run_http(config: ServerConfig) -> Result<NapcatRouter<HttpAdapter>> {
+    if config.record_frames {
+        record::init(record::RECORD_DIR)?;
+    }
+    let (adapter, subscribe) = network::HttpAdapter::new(&config);
+    let mut server = webhook::BotWebhookServer::new(config, adapter);
+    server.listen().await?;
+    Ok(NapcatRouter::new(subscribe, Box::new(server)))
+}
+
 pub mod logic_import {
     pub async fn handle_error<T, M>(
         ctx: &mut Context<T, M>,
@@ -32,6 +66,11 @@ pub mod logic_import {
         T: BotClient + BotHandler + std::fmt::Debug + Send + Sync + 'static,
         M: message::MessageType + std::fmt::Debug + Send + Sync + 'static,
     {
+        // 取消与超时属于正常流程，直接告知用户
+        if let Some(e) = err.downcast_ref::<WaitError>() {
+            ctx.send_message_async(message::from_str(e.to_string()));
+            return;
+        }
         ctx.send_message_async(message::from_str(format!(
             "Logic [{}] 运行出现错误: {}",
             fn_name, err
@@ -47,9 +86,16 @@ pub mod logic_import {
             event_request::Request,
         },
         network::BotClient,
+        router::{
+            args::{CommandArgs, Mention},
+            conversation::WaitError,
+            limit::{LimitScope, RateLimit},
+            permission::Permission,
+        },
         websocket::BotHandler,
     };
     pub use crate::config;
+    pub use helper::CommandArgs;
     pub use helper::handler;
     pub use helper::register_handler_with_help;
     pub use std::fmt;
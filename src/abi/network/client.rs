@@ -2,10 +2,12 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde::Serialize;
 use std::fmt;
+use tokio::sync::watch;
 
 use crate::abi::{
     echo::Echo,
     message::{Params, api},
+    network,
 };
 
 #[async_trait]
@@ -15,4 +17,9 @@ pub trait BotClient {
         params: T,
         echo: Echo,
     ) -> Result<api::ApiResponsePending<T::Response>>;
+
+    /// 与 Napcat 的连接状态，默认使用传输层维护的全局状态
+    fn connection(&self) -> watch::Receiver<bool> {
+        network::subscribe_connected()
+    }
 }
@@ -0,0 +1,14 @@
+use std::sync::LazyLock;
+use tokio::sync::watch;
+
+/// 与 Napcat 的连接状态，由传输层在连接建立与断开时更新
+static CONNECTED: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::Sender::new(false));
+
+pub fn set_connected(connected: bool) {
+    CONNECTED.send_replace(connected);
+}
+
+/// 订阅连接状态的变化
+pub fn subscribe_connected() -> watch::Receiver<bool> {
+    CONNECTED.subscribe()
+}
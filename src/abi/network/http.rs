@@ -0,0 +1,182 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::fmt;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{Utf8Bytes, http::StatusCode};
+use tracing::{debug, info, trace, warn};
+
+use crate::{
+    abi::{
+        echo::{Echo, echo_send_result},
+        message::{Event, Params, api},
+        network::{BotClient, napcat::dispatch_event, set_connected},
+        websocket::{AuthError, BotHandler},
+    },
+    config::ServerConfig,
+};
+
+/// 通过 Napcat HTTP 服务调用 API 的适配器
+///
+/// 每个动作以 POST `/{action}` 发出，响应补上 echo 后仍走 Echo 注册表，
+/// 因此调用方与 WebSocket 模式下的用法完全一致。
+#[derive(Debug)]
+pub struct HttpAdapter {
+    base_url: String,
+    access_token: Option<String>,
+    client: reqwest::Client,
+    handler: mpsc::UnboundedSender<Event>,
+}
+
+impl HttpAdapter {
+    pub fn new(config: &ServerConfig) -> (Self, mpsc::UnboundedReceiver<Event>) {
+        let (tx, rx) = mpsc::unbounded_channel::<Event>();
+        (
+            HttpAdapter {
+                base_url: format!("http://{}:{}", config.host, config.port),
+                access_token: config.access_token.clone(),
+                client: reqwest::Client::new(),
+                handler: tx,
+            },
+            rx,
+        )
+    }
+
+    async fn post<T: Params + Serialize>(&self, params: &T) -> Result<Value> {
+        let mut request = self
+            .client
+            .post(format!("{}/{}", self.base_url, T::ACTION))
+            .json(params);
+        if let Some(token) = &self.access_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            return Err(AuthError {
+                endpoint: T::ACTION,
+                status,
+            }
+            .into());
+        }
+
+        let body = response.bytes().await?;
+        match serde_json::from_slice::<Value>(&body) {
+            Ok(value @ Value::Object(_)) => Ok(value),
+            _ => {
+                // 非 OneBot 格式的响应（如 404 页面）统一转成失败响应
+                warn!("API {} 返回了无法解析的响应: {}", T::ACTION, status);
+                Ok(json!({
+                    "status": "failed",
+                    "retcode": status.as_u16(),
+                    "message": String::from_utf8_lossy(&body),
+                    "data": null,
+                }))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl BotClient for HttpAdapter {
+    async fn call_api<T: Params + Serialize + fmt::Debug>(
+        &self,
+        params: T,
+        echo: Echo,
+    ) -> Result<api::ApiResponsePending<T::Response>> {
+        debug!("调用 API: {}", T::ACTION);
+        trace!(?params);
+
+        let mut response = self.post(&params).await?;
+        response["echo"] = Value::String(echo.to_string());
+
+        // 响应已在手，注册后立即投递即可
+        let pending = api::ApiResponsePending::new(echo);
+        echo_send_result(&echo.to_string(), Utf8Bytes::from(response.to_string()));
+
+        Ok(pending)
+    }
+}
+
+#[async_trait]
+impl BotHandler for HttpAdapter {
+    async fn init(
+        &self,
+        _event: mpsc::UnboundedSender<String>,
+        _api: mpsc::UnboundedSender<String>,
+    ) -> Result<()> {
+        // HTTP 模式下 API 直接走请求，不需要发送通道
+        Ok(())
+    }
+
+    async fn handle_api(&self, message: Utf8Bytes) {
+        debug!("HTTP 模式下忽略 API 返回帧: {}", message);
+    }
+
+    async fn handle_event(&self, event: Utf8Bytes) {
+        dispatch_event(&self.handler, event);
+    }
+
+    async fn on_connect(&self) {
+        set_connected(true);
+        info!("HTTP 上报服务已就绪: {}", self.base_url);
+    }
+
+    async fn on_disconnect(&self) {
+        set_connected(false);
+        info!("HTTP 上报服务已停止。");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::router::context::call_checked;
+    use axum::{Router, routing::post};
+
+    /// 在空闲端口上启动模拟的 Napcat HTTP 服务
+    async fn serve(app: Router) -> Result<HttpAdapter> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        let config = ServerConfig {
+            port,
+            ..Default::default()
+        };
+        Ok(HttpAdapter::new(&config).0)
+    }
+
+    #[tokio::test]
+    async fn test_failed_response() -> Result<()> {
+        let adapter = serve(Router::new().route(
+            "/get_msg",
+            post(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "服务器内部错误") }),
+        ))
+        .await?;
+
+        let err = call_checked(&adapter, api::GetMsg::new(1), "获取消息")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("服务器内部错误"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized() -> Result<()> {
+        let adapter =
+            serve(Router::new().route("/get_msg", post(|| async { StatusCode::UNAUTHORIZED })))
+                .await?;
+
+        let Err(err) = adapter.call_api(api::GetMsg::new(1), Echo::new()).await else {
+            panic!("鉴权失败的请求没有返回错误");
+        };
+        assert!(err.downcast_ref::<AuthError>().is_some());
+
+        Ok(())
+    }
+}
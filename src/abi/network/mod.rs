@@ -1,5 +1,11 @@
 mod client;
+mod connection;
+mod http;
 mod napcat;
 
 pub use client::BotClient;
-pub use napcat::NapcatAdapter;
+pub use connection::{set_connected, subscribe_connected};
+pub use http::HttpAdapter;
+#[cfg(any(test, feature = "testing"))]
+pub(crate) use napcat::parse_event;
+pub use napcat::{NapcatAdapter, unknown_shape_count};
@@ -1,22 +1,31 @@
 use anyhow::Result;
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::fmt;
-use tokio::sync::{OnceCell, mpsc};
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::Utf8Bytes;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 use crate::abi::{
-    echo::{Echo, echo_send_result},
+    echo::{ConnectionLost, Echo, echo_send_result},
     message::{Event, Params, api},
-    network::BotClient,
+    network::{BotClient, set_connected},
+    record::{self, FrameKind},
     websocket::BotHandler,
 };
 
 #[derive(Debug)]
 pub struct NapcatAdapter {
-    event_sender: OnceCell<mpsc::UnboundedSender<String>>,
-    api_sender: OnceCell<mpsc::UnboundedSender<String>>,
+    // 每次重连都会替换为新的发送通道
+    event_sender: ArcSwapOption<mpsc::UnboundedSender<String>>,
+    api_sender: ArcSwapOption<mpsc::UnboundedSender<String>>,
     handler: mpsc::UnboundedSender<Event>,
 }
 
@@ -25,8 +34,8 @@ impl NapcatAdapter {
         let (tx, rx) = mpsc::unbounded_channel::<Event>();
         (
             NapcatAdapter {
-                event_sender: OnceCell::new(),
-                api_sender: OnceCell::new(),
+                event_sender: ArcSwapOption::empty(),
+                api_sender: ArcSwapOption::empty(),
                 handler: tx,
             },
             rx,
@@ -52,18 +61,71 @@ impl BotClient for NapcatAdapter {
         debug!("调用 API: {}", action);
         trace!(?api_send);
 
-        if let Some(sender) = self.api_sender.get() {
-            if let Err(e) = sender.send(msg) {
-                error!("发送 API 消息失败: {:?}", e);
+        // 先注册 Echo，避免响应早于注册到达而被丢弃
+        let pending = api::ApiResponsePending::new(echo);
+
+        let sent = match self.api_sender.load().as_deref() {
+            Some(sender) => sender
+                .send(msg)
+                .map_err(|e| error!("发送 API 消息失败: {:?}", e)),
+            None => {
+                error!("API 发送通道未初始化");
+                Err(())
             }
-        } else {
-            error!("API 发送通道未初始化");
+        };
+
+        if sent.is_err() {
+            pending.echo.cancel();
+            return Err(ConnectionLost.into());
         }
 
-        Ok(api::ApiResponsePending::new(echo))
+        Ok(pending)
     }
 }
 
+/// 自启动以来收到的未知事件结构数量
+static UNKNOWN_SHAPES: AtomicU64 = AtomicU64::new(0);
+
+pub fn unknown_shape_count() -> u64 {
+    UNKNOWN_SHAPES.load(Ordering::Relaxed)
+}
+
+/// 解析原始事件帧并交给路由器，HTTP 适配器共用此逻辑
+pub(super) fn dispatch_event(handler: &mpsc::UnboundedSender<Event>, event: Utf8Bytes) {
+    debug!("收到事件: {}", event);
+    trace!(?event);
+    record::record(FrameKind::Event, &event);
+
+    match parse_event(&event) {
+        Ok(evt) => {
+            if let Err(e) = handler.send(evt) {
+                error!("分发事件失败: {:?}", e);
+            }
+        }
+        Err(e) => {
+            error!("解析事件失败: {:?}", e);
+        }
+    }
+}
+
+/// 反序列化事件并统计其中的未知结构，回放与测试注入的事件也经过这里
+pub(crate) fn parse_event(event: &str) -> serde_json::Result<Event> {
+    let evt = serde_json::from_str::<Event>(event)?;
+    debug!("解析事件成功: {:?}", evt);
+    trace!(?evt);
+
+    let unknown = evt.count_unknown() as u64;
+    if unknown > 0 {
+        let total = UNKNOWN_SHAPES.fetch_add(unknown, Ordering::Relaxed) + unknown;
+        warn!(
+            "事件中包含 {} 个未知结构 (累计 {}): {}",
+            unknown, total, event
+        );
+    }
+
+    Ok(evt)
+}
+
 #[derive(Deserialize, Debug)]
 struct EchoOnly {
     echo: String,
@@ -76,8 +138,8 @@ impl BotHandler for NapcatAdapter {
         event: mpsc::UnboundedSender<String>,
         api: mpsc::UnboundedSender<String>,
     ) -> Result<()> {
-        self.event_sender.set(event)?;
-        self.api_sender.set(api)?;
+        self.event_sender.store(Some(Arc::new(event)));
+        self.api_sender.store(Some(Arc::new(api)));
 
         Ok(())
     }
@@ -85,6 +147,7 @@ impl BotHandler for NapcatAdapter {
     async fn handle_api(&self, message: Utf8Bytes) {
         debug!("收到API返回: {}", message);
         trace!(?message);
+        record::record(FrameKind::Api, &message);
 
         let echo_only = serde_json::from_slice::<EchoOnly>(message.as_bytes()).ok();
 
@@ -100,31 +163,18 @@ impl BotHandler for NapcatAdapter {
     }
 
     async fn handle_event(&self, event: Utf8Bytes) {
-        debug!("收到事件: {}", event);
-        trace!(?event);
-
-        let data = serde_json::from_slice::<Event>(event.as_bytes());
-
-        match data {
-            Ok(evt) => {
-                debug!("解析事件成功: {:?}", evt);
-                trace!(?evt);
-
-                if let Err(e) = self.handler.send(evt) {
-                    error!("分发事件失败: {:?}", e);
-                }
-            }
-            Err(e) => {
-                error!("解析事件失败: {:?}", e);
-            }
-        }
+        dispatch_event(&self.handler, event);
     }
 
     async fn on_connect(&self) {
+        set_connected(true);
         info!("连接到服务器。");
     }
 
     async fn on_disconnect(&self) {
+        set_connected(false);
+        self.event_sender.store(None);
+        self.api_sender.store(None);
         info!("已断开与服务器的连接。");
     }
 }
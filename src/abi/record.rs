@@ -0,0 +1,186 @@
+use crate::config::DATA_DIR;
+use anyhow::Result;
+use const_format::concatcp;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, mpsc};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::Utf8Bytes;
+use tracing::{error, info};
+
+pub const RECORD_DIR: &str = concatcp!(DATA_DIR, "/record");
+const RECORD_FILE: &str = "frames.jsonl";
+/// 单个文件超过此大小后轮转
+const MAX_FILE_BYTES: u64 = 32 * 1024 * 1024;
+/// 保留的历史文件数量
+const MAX_FILES: usize = 10;
+
+static RECORDER: OnceLock<mpsc::Sender<String>> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameKind {
+    Event,
+    Api,
+}
+
+/// 录制文件中的一行，原始帧以字符串保存，解析失败的帧也能原样留存
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RecordedFrame {
+    /// Unix 毫秒时间戳
+    pub ts: u64,
+    pub kind: FrameKind,
+    pub frame: String,
+}
+
+/// 启用录制，之后 `record` 收到的帧都会追加到 `dir` 下的 JSONL 文件
+pub fn init(dir: &'static str) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let (tx, rx) = mpsc::channel::<String>();
+    if RECORDER.set(tx).is_err() {
+        return Ok(());
+    }
+
+    std::thread::Builder::new()
+        .name("frame-recorder".to_string())
+        .spawn(move || {
+            let mut writer = RotatingWriter::new(Path::new(dir));
+            while let Ok(line) = rx.recv() {
+                // 批量写入当前积压的帧后再刷新
+                let mut result = writer.write_line(&line);
+                while let Ok(line) = rx.try_recv() {
+                    result = result.and_then(|_| writer.write_line(&line));
+                }
+                if let Err(e) = result.and_then(|_| writer.flush()) {
+                    error!("写入录制文件失败: {:?}", e);
+                }
+            }
+        })?;
+
+    info!("原始帧录制已启用: {}", dir);
+    Ok(())
+}
+
+/// 记录一帧，未启用录制时直接返回
+pub fn record(kind: FrameKind, frame: &Utf8Bytes) {
+    let Some(sender) = RECORDER.get() else {
+        return;
+    };
+
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let line = RecordedFrame {
+        ts,
+        kind,
+        frame: frame.to_string(),
+    };
+
+    match serde_json::to_string(&line) {
+        Ok(line) => {
+            let _ = sender.send(line);
+        }
+        Err(e) => error!("序列化录制帧失败: {:?}", e),
+    }
+}
+
+struct RotatingWriter {
+    dir: PathBuf,
+    file: Option<BufWriter<File>>,
+    written: u64,
+}
+
+impl RotatingWriter {
+    fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            file: None,
+            written: 0,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        if self.written >= MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => {
+                let path = self.dir.join(RECORD_FILE);
+                let file = OpenOptions::new().create(true).append(true).open(&path)?;
+                self.written = file.metadata()?.len();
+                self.file.insert(BufWriter::new(file))
+            }
+        };
+
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+        self.written += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.flush()?;
+        self.file = None;
+        self.written = 0;
+
+        let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        fs::rename(
+            self.dir.join(RECORD_FILE),
+            self.dir.join(format!("frames.{}.jsonl", ts)),
+        )?;
+
+        // 文件名中的时间戳等宽，按名称排序即按时间排序
+        let mut history = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("frames.") && n != RECORD_FILE)
+            })
+            .collect::<Vec<_>>();
+        history.sort();
+
+        let excess = history.len().saturating_sub(MAX_FILES);
+        for path in &history[..excess] {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("record-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir)?;
+
+        let mut writer = RotatingWriter::new(&dir);
+        writer.write_line(r#"{"a":1}"#)?;
+        writer.rotate()?;
+        writer.write_line(r#"{"a":2}"#)?;
+        writer.flush()?;
+
+        assert_eq!(fs::read_dir(&dir)?.count(), 2);
+        assert_eq!(fs::read_to_string(dir.join(RECORD_FILE))?, "{\"a\":2}\n");
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use crate::abi::{
+    fake::FakeBot,
+    record::{FrameKind, RecordedFrame},
+    websocket::BotHandler,
+};
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, BufReader},
+    time,
+};
+use tokio_tungstenite::tungstenite::Utf8Bytes;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplaySpeed {
+    /// 按录制时的时间间隔回放
+    Original,
+    /// 不等待，尽快回放
+    Fast,
+}
+
+/// 将录制文件中的事件帧重新喂给路由器
+///
+/// 事件经过与线上相同的反序列化与 `NapcatRouter::run` 流程，
+/// handler 发出的 API 调用由返回的 `FakeBot` 记录，不会触达真实的 Napcat。
+pub async fn replay(path: impl AsRef<Path>, speed: ReplaySpeed) -> Result<Arc<FakeBot>> {
+    let bot = FakeBot::spawn_router();
+    let mut lines = BufReader::new(File::open(path.as_ref()).await?).lines();
+
+    let mut last_ts = None;
+    let mut count = 0usize;
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let frame = match serde_json::from_str::<RecordedFrame>(&line) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("跳过无法解析的录制行: {:?}", e);
+                continue;
+            }
+        };
+        // API 返回由 FakeBot 重新生成，录制的响应无法与新的 echo 对应
+        if frame.kind != FrameKind::Event {
+            continue;
+        }
+
+        if speed == ReplaySpeed::Original
+            && let Some(last) = last_ts
+        {
+            time::sleep(Duration::from_millis(frame.ts.saturating_sub(last))).await;
+        }
+        last_ts = Some(frame.ts);
+
+        bot.handle_event(Utf8Bytes::from(frame.frame)).await;
+        count += 1;
+    }
+
+    info!("回放完成，共注入 {} 个事件", count);
+    Ok(bot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::record::RecordedFrame;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_replay() -> Result<()> {
+        let event = json!({
+            "post_type": "message",
+            "message_type": "private",
+            "time": 1700000000,
+            "self_id": 10000,
+            "sub_type": "friend",
+            "message_id": 1,
+            "user_id": 456,
+            "raw_message": "/echo 回放",
+            "font": 0,
+            "sender": { "user_id": 456, "nickname": "TestUser" },
+            "message": [{ "type": "text", "data": { "text": "/echo 回放" } }],
+        });
+        let lines = [
+            RecordedFrame {
+                ts: 1,
+                kind: FrameKind::Api,
+                frame: r#"{"status":"ok","echo":"1"}"#.to_string(),
+            },
+            RecordedFrame {
+                ts: 2,
+                kind: FrameKind::Event,
+                frame: event.to_string(),
+            },
+        ]
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<serde_json::Result<Vec<_>>>()?
+        .join("\n");
+
+        let path = std::env::temp_dir().join(format!("replay-{}.jsonl", uuid::Uuid::new_v4()));
+        tokio::fs::write(&path, lines).await?;
+
+        let bot = replay(&path, ReplaySpeed::Fast).await?;
+        let call = bot
+            .wait_for("send_private_forward_msg", Duration::from_secs(5))
+            .await
+            .expect("回放的指令没有被处理");
+        assert!(call.params.to_string().contains("你说的是: /echo 回放"));
+
+        tokio::fs::remove_file(&path).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+use crate::abi::echo::echo_fail_all;
+use crate::abi::utils::check_token;
+use crate::abi::websocket::{BotHandler, BotTransport};
+use crate::config::ServerConfig;
+use anyhow::Result;
+use futures_util::StreamExt;
+use futures_util::sink::SinkExt;
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_tungstenite::{
+    accept_hdr_async,
+    tungstenite::{
+        Message, Utf8Bytes,
+        handshake::server::{ErrorResponse, Request, Response},
+        http::StatusCode,
+    },
+};
+use tracing::{debug, error, info, warn};
+
+static CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 反向连接的角色，对应 OneBot 的 Universal 与分离的 Event/API 端点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Universal,
+    Event,
+    Api,
+}
+
+impl Role {
+    /// 优先使用 `X-Client-Role` 头，缺省时按路径后缀判断
+    fn from_request(request: &Request) -> Self {
+        if let Some(role) = request
+            .headers()
+            .get("X-Client-Role")
+            .and_then(|v| v.to_str().ok())
+        {
+            match role.to_ascii_lowercase().as_str() {
+                "event" => return Role::Event,
+                "api" => return Role::Api,
+                "universal" => return Role::Universal,
+                _ => {}
+            }
+        }
+
+        let path = request.uri().path().trim_end_matches('/');
+        if path.ends_with("/event") {
+            Role::Event
+        } else if path.ends_with("/api") {
+            Role::Api
+        } else {
+            Role::Universal
+        }
+    }
+
+    fn has_event(self) -> bool {
+        matches!(self, Role::Universal | Role::Event)
+    }
+
+    fn has_api(self) -> bool {
+        matches!(self, Role::Universal | Role::Api)
+    }
+}
+
+/// Universal 连接上事件与 API 响应混在一起，靠 `post_type` 区分
+#[derive(Deserialize)]
+struct FrameKind {
+    post_type: Option<IgnoredAny>,
+}
+
+fn is_event_frame(frame: &Utf8Bytes) -> bool {
+    serde_json::from_slice::<FrameKind>(frame.as_bytes())
+        .map(|k| k.post_type.is_some())
+        .unwrap_or(false)
+}
+
+type Slot = Option<(u64, mpsc::UnboundedSender<String>)>;
+
+/// 当前挂在 handler 上的事件与 API 通道，分离模式下两者来自不同连接
+struct Slots {
+    event: Slot,
+    api: Slot,
+    connected: bool,
+}
+
+struct Shared<T: BotHandler> {
+    handler: Arc<T>,
+    access_token: Option<String>,
+    slots: Mutex<Slots>,
+}
+
+impl<T: BotHandler> Shared<T> {
+    async fn attach(&self, role: Role, id: u64, sender: mpsc::UnboundedSender<String>) {
+        let mut slots = self.slots.lock().await;
+        if role.has_event() {
+            slots.event = Some((id, sender.clone()));
+        }
+        if role.has_api() {
+            slots.api = Some((id, sender));
+        }
+
+        if let (Some((_, event)), Some((_, api))) = (&slots.event, &slots.api) {
+            if let Err(e) = self.handler.init(event.clone(), api.clone()).await {
+                error!("初始化反向连接通道失败: {:?}", e);
+                return;
+            }
+            if !slots.connected {
+                slots.connected = true;
+                self.handler.on_connect().await;
+            }
+        }
+    }
+
+    async fn detach(&self, id: u64) {
+        let mut slots = self.slots.lock().await;
+        if slots.event.as_ref().is_some_and(|(i, _)| *i == id) {
+            slots.event = None;
+        }
+        if slots.api.as_ref().is_some_and(|(i, _)| *i == id) {
+            slots.api = None;
+            echo_fail_all();
+        }
+
+        if slots.connected && (slots.event.is_none() || slots.api.is_none()) {
+            slots.connected = false;
+            self.handler.on_disconnect().await;
+        }
+    }
+
+    async fn serve(self: Arc<Self>, stream: TcpStream) -> Result<()> {
+        let mut role = Role::Universal;
+        let token = self.access_token.as_deref();
+
+        // ErrorResponse 的大小由 tungstenite 的 Callback 签名决定
+        #[allow(clippy::result_large_err)]
+        let callback = |request: &Request, response: Response| {
+            if !check_token(request, token) {
+                let mut reject = ErrorResponse::new(Some("access_token 不正确".to_string()));
+                *reject.status_mut() = StatusCode::UNAUTHORIZED;
+                return Err(reject);
+            }
+            role = Role::from_request(request);
+            Ok(response)
+        };
+        let ws_stream = accept_hdr_async(stream, callback).await?;
+
+        let id = CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+        info!("Napcat 已反向连接, 角色: {:?}, 连接编号: {}", role, id);
+
+        let (mut write, mut read) = ws_stream.split();
+        let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+
+        let write_task = tokio::spawn(async move {
+            while let Some(msg) = receiver.recv().await {
+                if let Err(e) = write.send(Message::Text(msg.into())).await {
+                    error!("传输Message失败通过 WsWriter: {:?}", e);
+                    break;
+                }
+            }
+        });
+
+        self.attach(role, id, sender).await;
+
+        while let Some(message) = read.next().await {
+            if let Ok(Message::Text(msg)) = message {
+                let is_event = match role {
+                    Role::Event => true,
+                    Role::Api => false,
+                    Role::Universal => is_event_frame(&msg),
+                };
+
+                let h = self.handler.clone();
+                tokio::spawn(async move {
+                    if is_event {
+                        h.handle_event(msg).await;
+                    } else {
+                        h.handle_api(msg).await;
+                    }
+                });
+            }
+        }
+
+        write_task.abort();
+        warn!("反向连接已断开, 连接编号: {}", id);
+        self.detach(id).await;
+
+        Ok(())
+    }
+}
+
+/// 反向 WebSocket 服务端，在 host:port 上等待 Napcat 连接
+pub struct BotReverseServer<T: BotHandler> {
+    config: ServerConfig,
+    shared: Arc<Shared<T>>,
+
+    accept_task: Option<JoinHandle<()>>,
+}
+
+impl<T: BotHandler> BotReverseServer<T> {
+    pub fn new(config: ServerConfig, handler: T) -> Self {
+        let shared = Arc::new(Shared {
+            handler: Arc::new(handler),
+            access_token: config.access_token.clone(),
+            slots: Mutex::new(Slots {
+                event: None,
+                api: None,
+                connected: false,
+            }),
+        });
+
+        BotReverseServer {
+            config,
+            shared,
+            accept_task: None,
+        }
+    }
+
+    pub async fn listen(&mut self) -> Result<()> {
+        let listener = TcpListener::bind((self.config.host.as_str(), self.config.port)).await?;
+        info!(
+            "反向 WebSocket 服务已启动，等待 Napcat 连接... {}:{}",
+            self.config.host, self.config.port
+        );
+        debug!(?self.config);
+
+        let shared = self.shared.clone();
+        self.accept_task = Some(tokio::spawn(async move {
+            // 终止监听任务时 JoinSet 被丢弃，所有连接随之终止
+            let mut connections = JoinSet::new();
+            loop {
+                tokio::select! {
+                    accepted = listener.accept() => match accepted {
+                        Ok((stream, addr)) => {
+                            debug!("收到反向连接请求: {}", addr);
+                            connections.spawn(shared.clone().serve(stream));
+                        }
+                        Err(e) => error!("接受反向连接失败: {:?}", e),
+                    },
+                    Some(joined) = connections.join_next() => {
+                        if let Ok(Err(e)) = joined {
+                            warn!("反向连接握手或处理失败: {:?}", e);
+                        }
+                    }
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self) {
+        let Some(task) = self.accept_task.take() else {
+            return;
+        };
+        task.abort();
+        echo_fail_all();
+
+        let handler = self.shared.handler.clone();
+        tokio::spawn(async move {
+            handler.on_disconnect().await;
+        });
+        info!("反向 WebSocket 服务已关闭");
+    }
+}
+
+impl<T: BotHandler> BotTransport<T> for BotReverseServer<T> {
+    fn handler(&self) -> Arc<T> {
+        self.shared.handler.clone()
+    }
+
+    fn disconnect(&mut self) {
+        BotReverseServer::disconnect(self);
+    }
+}
+
+impl<T: BotHandler> Drop for BotReverseServer<T> {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_tungstenite::tungstenite::http::header;
+
+    #[test]
+    fn test_role_and_token() -> Result<()> {
+        let request = Request::builder()
+            .uri("/onebot/v11/ws/api?access_token=secret")
+            .body(())?;
+        assert_eq!(Role::from_request(&request), Role::Api);
+        assert!(check_token(&request, Some("secret")));
+        assert!(!check_token(&request, Some("other")));
+
+        let request = Request::builder()
+            .uri("/onebot/v11/ws")
+            .header("X-Client-Role", "Event")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(())?;
+        assert_eq!(Role::from_request(&request), Role::Event);
+        assert!(check_token(&request, Some("secret")));
+
+        let request = Request::builder().uri("/").body(())?;
+        assert_eq!(Role::from_request(&request), Role::Universal);
+        assert!(check_token(&request, None));
+        assert!(!check_token(&request, Some("secret")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_kind() {
+        assert!(is_event_frame(&Utf8Bytes::from_static(
+            r#"{"post_type":"meta_event","time":1}"#
+        )));
+        assert!(!is_event_frame(&Utf8Bytes::from_static(
+            r#"{"status":"ok","retcode":0,"echo":"1"}"#
+        )));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::abi::logic_import::Message;
+use crate::abi::message::{MessageReceive, message_body::SegmentReceive};
+use crate::abi::router::command::split_command;
+use std::{fmt, str::FromStr};
+
+/// 指令参数解析，由 `#[derive(CommandArgs)]` 实现
+pub trait CommandArgs: Sized {
+    /// 自动生成的参数用法，如 `<id> [--all] [-k <key>]`
+    const USAGE: &'static str;
+
+    fn parse(tokens: Vec<String>) -> Result<Self, ArgsError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgsError {
+    Missing(&'static str),
+    MissingValue(&'static str),
+    Invalid { name: &'static str, reason: String },
+    TooMany(String),
+    UnknownOption(String),
+    UnclosedQuote,
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsError::Missing(name) => write!(f, "缺少参数 <{}>", name),
+            ArgsError::MissingValue(option) => write!(f, "选项 {} 需要一个值", option),
+            ArgsError::Invalid { name, reason } => write!(f, "参数 <{}> 无效: {}", name, reason),
+            ArgsError::TooMany(arg) => write!(f, "多余的参数: {}", arg),
+            ArgsError::UnknownOption(option) => write!(f, "未知的选项: {}", option),
+            ArgsError::UnclosedQuote => write!(f, "引号没有闭合"),
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+/// 被 @ 的用户，也接受直接输入的 QQ 号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mention(pub i64);
+
+impl FromStr for Mention {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix('@').unwrap_or(s).parse::<i64>().map(Mention)
+    }
+}
+
+/// 供派生宏使用，解析单个参数值
+pub fn parse_value<T>(name: &'static str, value: &str) -> Result<T, ArgsError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value.parse::<T>().map_err(|e| ArgsError::Invalid {
+        name,
+        reason: e.to_string(),
+    })
+}
+
+/// 按空白切分参数，单双引号内的内容作为一个整体，引号内可用 `\` 转义
+pub fn tokenize(text: &str) -> Result<Vec<String>, ArgsError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                let quote = c;
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some(c) if c == quote => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => current.push(escaped),
+                            None => return Err(ArgsError::UnclosedQuote),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(ArgsError::UnclosedQuote),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+/// 取出消息中指令（含别名）之后的参数，@ 消息段转换为 `@QQ号`
+///
+/// 与指令分发一致，指令从第一个文本段开始，此前的 @（如引用回复时自动附带的 @）不计入参数。
+pub fn command_tokens(message: &Message) -> Result<Vec<String>, ArgsError> {
+    let receive = match message {
+        Message::Private(p) => &p.message,
+        Message::Group(g) => &g.message,
+    };
+    let segments = match receive {
+        MessageReceive::Array(arr) => arr.as_slice(),
+        MessageReceive::Single(seg) => std::slice::from_ref(seg),
+    };
+
+    let mut text = String::new();
+    let mut started = false;
+    for seg in segments {
+        match seg {
+            SegmentReceive::Text(t) => {
+                text.push_str(&t.text);
+                started = true;
+            }
+            SegmentReceive::At(at) if started => {
+                text.push_str(" @");
+                text.push_str(&at.qq);
+                text.push(' ');
+            }
+            _ => {}
+        }
+    }
+
+    let (_, rest) = split_command(text.trim_start());
+    tokenize(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(r#"a  "b c" 'd\'e' -k v"#).unwrap(),
+            vec!["a", "b c", "d'e", "-k", "v"]
+        );
+        assert_eq!(tokenize(r#""""#).unwrap(), vec![""]);
+        assert_eq!(tokenize(r#"a "b"#), Err(ArgsError::UnclosedQuote));
+        assert_eq!("@123".parse::<Mention>().unwrap(), Mention(123));
+    }
+
+    #[test]
+    fn test_command_tokens() {
+        // 引用回复时 QQ 会在指令前自动加上被引用者的 @
+        let message = serde_json::from_value::<Message>(serde_json::json!({
+            "message_type": "group",
+            "time": 1700000000,
+            "self_id": 10000,
+            "sub_type": "normal",
+            "message_id": 1,
+            "group_id": 123,
+            "user_id": 456,
+            "anonymous": null,
+            "raw_message": "",
+            "font": 0,
+            "sender": { "user_id": 456, "nickname": "TestUser", "role": "member" },
+            "message": [
+                { "type": "reply", "data": { "id": "100" } },
+                { "type": "at", "data": { "qq": "789" } },
+                { "type": "text", "data": { "text": " /download 42 " } },
+                { "type": "at", "data": { "qq": "321" } },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(command_tokens(&message).unwrap(), vec!["42", "@321"]);
+    }
+
+    #[derive(helper::CommandArgs, Debug, PartialEq)]
+    struct TestArgs {
+        user: Mention,
+        #[arg(default = "1")]
+        count: i64,
+        #[arg(rest)]
+        reason: Option<String>,
+        #[arg(long, short = 'a')]
+        all: bool,
+        #[arg(long = "key")]
+        key: Option<String>,
+    }
+
+    fn parse(text: &str) -> Result<TestArgs, ArgsError> {
+        TestArgs::parse(tokenize(text)?)
+    }
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(
+            TestArgs::USAGE,
+            "<user> [count] [reason...] [--all|-a] [--key <key>]"
+        );
+        assert_eq!(
+            parse(r#"@123 -5 "刷屏" 广告 -a --key v"#).unwrap(),
+            TestArgs {
+                user: Mention(123),
+                count: -5,
+                reason: Some("刷屏 广告".to_string()),
+                all: true,
+                key: Some("v".to_string()),
+            }
+        );
+        assert_eq!(
+            parse("123").unwrap(),
+            TestArgs {
+                user: Mention(123),
+                count: 1,
+                reason: None,
+                all: false,
+                key: None,
+            }
+        );
+        assert_eq!(parse(""), Err(ArgsError::Missing("user")));
+        assert_eq!(parse("1 --key"), Err(ArgsError::MissingValue("--key")));
+        assert_eq!(
+            parse("1 --x"),
+            Err(ArgsError::UnknownOption("--x".to_string()))
+        );
+        assert!(matches!(
+            parse("abc"),
+            Err(ArgsError::Invalid { name: "user", .. })
+        ));
+    }
+}
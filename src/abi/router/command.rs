@@ -0,0 +1,101 @@
+/// 所有 handler 的指令名与别名总数
+pub const fn count(commands: &[&[&str]]) -> usize {
+    let mut total = 0;
+    let mut i = 0;
+    while i < commands.len() {
+        total += commands[i].len();
+        i += 1;
+    }
+    total
+}
+
+const fn compare(a: &str, b: &str) -> core::cmp::Ordering {
+    use core::cmp::Ordering;
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut i = 0;
+    while i < a.len() && i < b.len() {
+        if a[i] < b[i] {
+            return Ordering::Less;
+        }
+        if a[i] > b[i] {
+            return Ordering::Greater;
+        }
+        i += 1;
+    }
+    if a.len() < b.len() {
+        Ordering::Less
+    } else if a.len() > b.len() {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// 生成指令名到 handler 序号的静态表，`commands[i]` 对应序号为 `i` 的 handler
+///
+/// 表在编译期排序，运行时二分查找，不产生任何分配；
+/// 两个 handler 注册了相同的指令名或别名时编译期直接报错。
+pub const fn build<const N: usize>(commands: &[&[&'static str]]) -> [(&'static str, usize); N] {
+    let mut table = [("", 0usize); N];
+    let mut len = 0;
+    let mut i = 0;
+    while i < commands.len() {
+        let mut j = 0;
+        while j < commands[i].len() {
+            // 插入排序，同时检查重复
+            let name = commands[i][j];
+            let mut pos = len;
+            while pos > 0 {
+                match compare(table[pos - 1].0, name) {
+                    core::cmp::Ordering::Greater => {
+                        table[pos] = table[pos - 1];
+                        pos -= 1;
+                    }
+                    core::cmp::Ordering::Equal => panic!("指令名或别名冲突"),
+                    core::cmp::Ordering::Less => break,
+                }
+            }
+            table[pos] = (name, i);
+            len += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+pub fn lookup(table: &[(&'static str, usize)], token: &str) -> Option<usize> {
+    table
+        .binary_search_by(|(name, _)| name.cmp(&token))
+        .ok()
+        .map(|i| table[i].1)
+}
+
+/// 指令名之后的文本，指令名以空白或文本结尾为边界
+pub fn split_command(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(pos) => (&text[..pos], &text[pos..]),
+        None => (text, ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: &[&[&str]] = &[&["test", "测试"], &["get_test"], &["echo", "e"]];
+    const TABLE: [(&str, usize); count(COMMANDS)] = build(COMMANDS);
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup(&TABLE, "test"), Some(0));
+        assert_eq!(lookup(&TABLE, "测试"), Some(0));
+        assert_eq!(lookup(&TABLE, "get_test"), Some(1));
+        assert_eq!(lookup(&TABLE, "e"), Some(2));
+        assert_eq!(lookup(&TABLE, "testfoo"), None);
+        assert_eq!(lookup(&TABLE, ""), None);
+
+        assert_eq!(split_command("test 1 2"), ("test", " 1 2"));
+        assert_eq!(split_command("test"), ("test", ""));
+    }
+}
@@ -4,13 +4,110 @@ use crate::abi::message::MessageSend;
 use crate::abi::message::Sender;
 use crate::abi::message::Type;
 use crate::abi::message::api;
+use crate::abi::message::message_body::SegmentSend;
 use crate::abi::message::{MessageType, Target};
 use crate::abi::network::BotClient;
+use crate::abi::router::conversation::{self, WaitError};
+use crate::abi::router::outbound::{self, Priority};
+use crate::abi::router::settings::{self, ReplyStyle};
 use crate::abi::websocket::BotHandler;
+use crate::config;
 use anyhow::Result;
+use serde::Deserialize;
 use std::fmt;
 use std::sync::Arc;
-use tracing::{error, info, trace};
+use std::time::Duration;
+use tokio::time;
+use tracing::{Span, error, info, trace};
+
+/// 群设置为合并转发时，不超过该字数的单条纯文本回复直接以普通消息发送
+const SHORT_REPLY_CHARS: usize = 100;
+
+/// 调用动作并检查结果状态，成功时返回响应中的数据，`action` 用于错误提示
+pub async fn call_checked<T, P, D>(client: &T, params: P, action: &str) -> Result<Option<D>>
+where
+    T: BotClient + Sync,
+    P: api::Params<Response = api::ApiResponse<D>> + fmt::Debug,
+    D: api::Data + fmt::Debug + for<'de> Deserialize<'de>,
+{
+    let call = client.call_api(params, Echo::new()).await?;
+    let res = call.wait_echo().await?;
+    trace!(?res);
+    match res.status {
+        api::Status::Ok => Ok(res.data),
+        api::Status::Failed => Err(anyhow::anyhow!(
+            "{}失败: {:?}",
+            action,
+            res.message.unwrap_or("未知错误".to_string())
+        )),
+        api::Status::Async => Err(anyhow::anyhow!("{}异步处理中", action)),
+    }
+}
+
+/// 不依赖 Context 向任意目标发送一条普通消息，供定时任务等主动发送的场景使用
+pub async fn send_message_to<T: BotClient + Sync>(
+    client: &T,
+    target: Target,
+    message: MessageSend,
+) -> Result<()> {
+    send_message_with(client, target, message, Priority::Bulk).await
+}
+
+/// 经过发送队列发送普通消息，过长的消息拆分为多条依次发送
+async fn send_message_with<T: BotClient + Sync>(
+    client: &T,
+    target: Target,
+    message: MessageSend,
+    priority: Priority,
+) -> Result<()> {
+    let max_chars = config::current().outbound.max_message_chars;
+    for chunk in outbound::split(message, max_chars) {
+        let chunk = &chunk;
+        match target {
+            Target::Group(group_id) => {
+                let res = outbound::dispatch(target, priority, move || async move {
+                    let params = api::SendGroupMessageParams::new(group_id, chunk.clone());
+                    client
+                        .call_api(params, Echo::new())
+                        .await?
+                        .wait_echo()
+                        .await
+                })
+                .await?;
+                trace!(?res);
+                match res.status {
+                    api::Status::Ok => {}
+                    api::Status::Failed => anyhow::bail!(
+                        "发送群消息失败: {:?}",
+                        res.message.unwrap_or("未知错误".to_string())
+                    ),
+                    api::Status::Async => anyhow::bail!("发送群消息异步处理中"),
+                }
+            }
+            Target::Private(user_id) => {
+                let res = outbound::dispatch(target, priority, move || async move {
+                    let params = api::SendPrivateMessageParams::new(user_id, chunk.clone());
+                    client
+                        .call_api(params, Echo::new())
+                        .await?
+                        .wait_echo()
+                        .await
+                })
+                .await?;
+                trace!(?res);
+                match res.status {
+                    api::Status::Ok => {}
+                    api::Status::Failed => anyhow::bail!(
+                        "发送私聊消息失败: {:?}",
+                        res.message.unwrap_or("未知错误".to_string())
+                    ),
+                    api::Status::Async => anyhow::bail!("发送私聊消息异步处理中"),
+                }
+            }
+        }
+    }
+    Ok(())
+}
 
 #[derive(Debug)]
 pub struct Context<
@@ -24,6 +121,10 @@ pub struct Context<
     pub message_text: Arc<str>,
     pub target: Target,
     pub is_echo: bool,
+    /// handler 任务所在的 span，由中间件设置
+    pub span: Span,
+    /// handler 指定的回复方式，为 None 时使用群设置
+    pub reply_style: Option<ReplyStyle>,
     send_msg: Option<Arc<Message>>,
 }
 
@@ -41,6 +142,8 @@ impl<
             message_text: self.message_text.clone(),
             target: self.target,
             is_echo: self.is_echo,
+            span: self.span.clone(),
+            reply_style: self.reply_style,
             send_msg: self.send_msg.clone(),
         }
     }
@@ -74,6 +177,8 @@ impl<
             message_list,
             message_text: Arc::from(message_text),
             is_echo: false,
+            span: Span::none(),
+            reply_style: None,
             send_msg: msg,
         }
     }
@@ -82,37 +187,24 @@ impl<
         self.is_echo = true;
     }
 
+    pub fn set_reply_style(&mut self, style: ReplyStyle) {
+        self.reply_style = Some(style);
+    }
+
+    /// 回复当前目标，排队时优先于主动发送的消息
     pub async fn send_message(&self, message: MessageSend) -> Result<()> {
-        match self.target {
-            Target::Group(group_id) => {
-                let params = api::SendGroupMessageParams::new(group_id, message);
-                let call = self.client.call_api(params, Echo::new()).await?;
-                let res = call.wait_echo().await?;
-                trace!(?res);
-                match res.status {
-                    api::Status::Ok => Ok(()),
-                    api::Status::Failed => Err(anyhow::anyhow!(
-                        "发送群消息失败: {:?}",
-                        res.message.unwrap_or("未知错误".to_string())
-                    )),
-                    api::Status::Async => Err(anyhow::anyhow!("发送群消息异步处理中")),
-                }
-            }
-            Target::Private(user_id) => {
-                let params = api::SendPrivateMessageParams::new(user_id, message);
-                let call = self.client.call_api(params, Echo::new()).await?;
-                let res = call.wait_echo().await?;
-                trace!(?res);
-                match res.status {
-                    api::Status::Ok => Ok(()),
-                    api::Status::Failed => Err(anyhow::anyhow!(
-                        "发送私聊消息失败: {:?}",
-                        res.message.unwrap_or("未知错误".to_string())
-                    )),
-                    api::Status::Async => Err(anyhow::anyhow!("发送私聊消息异步处理中")),
-                }
-            }
-        }
+        send_message_with(
+            self.client.as_ref(),
+            self.target,
+            message,
+            Priority::Interactive,
+        )
+        .await
+    }
+
+    /// 直接向任意目标发送一条普通消息，如把请求转交给管理员私聊
+    pub async fn send_message_to(&self, target: Target, message: MessageSend) -> Result<()> {
+        send_message_to(self.client.as_ref(), target, message).await
     }
 
     pub fn send_message_async(&mut self, message: MessageSend) {
@@ -131,31 +223,200 @@ impl<
         self.target
     }
 
+    /// 等待同一用户在同一目标中发送的下一条满足 `filter` 的消息
+    ///
+    /// 等待前会先发出已缓存的消息（通常是提问）；被认领的消息不会再触发指令与 LLM 回复。
+    /// 用户发送“取消”或超时时返回 `WaitError`。
+    pub async fn wait_for_next_message<F>(
+        &mut self,
+        filter: F,
+        timeout: Duration,
+    ) -> Result<Arc<Message>>
+    where
+        F: Fn(&Message) -> bool + Send + Sync + 'static,
+    {
+        let user_id = self
+            .sender
+            .user_id
+            .ok_or(anyhow::anyhow!("获取用户ID失败"))?;
+        let (id, reply) = conversation::register(self.target, user_id, Box::new(filter));
+        self.flush().await;
+
+        match time::timeout(timeout, reply).await {
+            Ok(Ok(Some(message))) => Ok(message),
+            Ok(Ok(None)) | Ok(Err(_)) => Err(WaitError::Cancelled.into()),
+            Err(_) => {
+                conversation::unregister(self.target, user_id, id);
+                Err(WaitError::Timeout.into())
+            }
+        }
+    }
+
+    /// 当前群号，`action` 用于在私聊中调用时给出错误提示
+    fn group_id(&self, action: &str) -> Result<i64> {
+        match self.target {
+            Target::Group(group_id) => Ok(group_id),
+            Target::Private(_) => Err(anyhow::anyhow!("只能在群聊中{}，当前目标不是群聊", action)),
+        }
+    }
+
+    async fn call_checked<P, D>(&self, params: P, action: &str) -> Result<Option<D>>
+    where
+        P: api::Params<Response = api::ApiResponse<D>> + fmt::Debug,
+        D: api::Data + fmt::Debug + for<'de> Deserialize<'de>,
+    {
+        call_checked(self.client.as_ref(), params, action).await
+    }
+
+    /// 调用不返回数据的动作
+    async fn call_action<P, D>(&self, params: P, action: &str) -> Result<()>
+    where
+        P: api::Params<Response = api::ApiResponse<D>> + fmt::Debug,
+        D: api::Data + fmt::Debug + for<'de> Deserialize<'de>,
+    {
+        self.call_checked(params, action).await.map(|_| ())
+    }
+
+    /// 调用查询类动作，响应中没有数据时视为失败
+    async fn call_query<P, D>(&self, params: P, action: &str) -> Result<D>
+    where
+        P: api::Params<Response = api::ApiResponse<D>> + fmt::Debug,
+        D: api::Data + fmt::Debug + for<'de> Deserialize<'de>,
+    {
+        self.call_checked(params, action)
+            .await?
+            .ok_or(anyhow::anyhow!("{}失败: 响应中没有数据", action))
+    }
+
     pub async fn set_title(&self, title: String) -> Result<()> {
-        let params = api::SpecialTitle::new(
-            match self.target {
-                Target::Group(group_id) => group_id,
-                Target::Private(_) => {
-                    return Err(anyhow::anyhow!(
-                        "只能在群聊中设置特殊头衔，当前目标不是群聊"
-                    ));
-                }
-            },
-            self.sender.user_id.unwrap_or(0),
-            title,
-        );
-        let call = self.client.call_api(params, Echo::new()).await?;
-        let res = call.wait_echo().await?;
-        trace!(?res);
-        match res.status {
-            api::Status::Ok => Ok(()),
-            api::Status::Failed => Err(anyhow::anyhow!(
-                "设置特殊头衔失败: {:?}",
-                res.message.unwrap_or("未知错误".to_string())
-            )),
-            api::Status::Async => Err(anyhow::anyhow!("设置特殊头衔异步处理中")),
+        let group_id = self.group_id("设置特殊头衔")?;
+        let params = api::SpecialTitle::new(group_id, self.sender.user_id.unwrap_or(0), title);
+        self.call_action(params, "设置特殊头衔").await
+    }
+
+    /// 撤回消息
+    pub async fn recall(&self, message_id: i64) -> Result<()> {
+        self.call_action(api::DeleteMsg::new(message_id), "撤回消息")
+            .await
+    }
+
+    /// 在当前群禁言成员，`duration` 为 0 时解除禁言
+    pub async fn mute(&self, user_id: i64, duration: Duration) -> Result<()> {
+        let group_id = self.group_id("禁言")?;
+        let params = api::SetGroupBan::new(group_id, user_id, duration.as_secs());
+        self.call_action(params, "禁言").await
+    }
+
+    /// 开启或关闭当前群的全员禁言
+    pub async fn mute_all(&self, enable: bool) -> Result<()> {
+        let group_id = self.group_id("全员禁言")?;
+        let params = api::SetGroupWholeBan::new(group_id, enable);
+        self.call_action(params, "全员禁言").await
+    }
+
+    /// 将成员移出当前群，`reject_add_request` 为 true 时不再接受此人的加群申请
+    pub async fn kick(&self, user_id: i64, reject_add_request: bool) -> Result<()> {
+        let group_id = self.group_id("移出成员")?;
+        let params = api::SetGroupKick::new(group_id, user_id, reject_add_request);
+        self.call_action(params, "移出成员").await
+    }
+
+    /// 设置成员在当前群的群名片，为空时删除群名片
+    pub async fn set_card(&self, user_id: i64, card: String) -> Result<()> {
+        let group_id = self.group_id("设置群名片")?;
+        let params = api::SetGroupCard::new(group_id, user_id, card);
+        self.call_action(params, "设置群名片").await
+    }
+
+    pub async fn set_essence(&self, message_id: i64) -> Result<()> {
+        self.call_action(api::SetEssenceMsg::new(message_id), "设置精华消息")
+            .await
+    }
+
+    pub async fn delete_essence(&self, message_id: i64) -> Result<()> {
+        self.call_action(api::DeleteEssenceMsg::new(message_id), "移除精华消息")
+            .await
+    }
+
+    /// 获取单条消息，可用于解析 `Reply` 消息段引用的消息
+    pub async fn get_msg(&self, message_id: i64) -> Result<Message> {
+        self.call_query(api::GetMsg::new(message_id), "获取消息")
+            .await
+    }
+
+    /// 获取合并转发中的各条消息，`id` 为 `Forward` 消息段中的 id
+    pub async fn get_forward_msg(&self, id: String) -> Result<Vec<api::ForwardNode>> {
+        let data = self
+            .call_query(api::GetForwardMsg::new(id), "获取合并转发")
+            .await?;
+        Ok(data.nodes())
+    }
+
+    /// 当前群的成员列表
+    pub async fn get_group_member_list(&self) -> Result<Vec<api::GroupMemberInfoData>> {
+        let group_id = self.group_id("获取群成员列表")?;
+        self.call_query(api::GroupMemberList::new(group_id, false), "获取群成员列表")
+            .await
+    }
+
+    /// Bot 加入的全部群
+    pub async fn get_group_list(&self) -> Result<Vec<api::GetGroupInfoData>> {
+        self.call_query(api::GroupList::new(false), "获取群列表")
+            .await
+    }
+
+    pub async fn get_friend_list(&self) -> Result<Vec<api::FriendData>> {
+        self.call_query(api::FriendList::new(false), "获取好友列表")
+            .await
+    }
+
+    /// 当前群文件中某个文件夹下的文件与子文件夹，`folder_id` 为 None 时列出根目录
+    pub async fn get_group_files(&self, folder_id: Option<String>) -> Result<api::GroupFilesData> {
+        let group_id = self.group_id("获取群文件列表")?;
+        match folder_id {
+            Some(folder_id) => {
+                let params = api::GroupFilesByFolder::new(group_id, folder_id);
+                self.call_query(params, "获取群文件列表").await
+            }
+            None => {
+                self.call_query(api::GroupRootFiles::new(group_id), "获取群文件列表")
+                    .await
+            }
         }
     }
+
+    /// 当前群中某个群文件的下载链接
+    pub async fn get_group_file_url(&self, file: &api::GroupFile) -> Result<String> {
+        let group_id = self.group_id("获取群文件链接")?;
+        let params = api::GroupFileUrl::new(group_id, file.file_id.clone(), file.busid);
+        let data = self.call_query(params, "获取群文件链接").await?;
+        Ok(data.url)
+    }
+
+    /// 上传文件到当前群，`file` 可以是 Napcat 所在机器上的路径、网络地址或 base64:// 内容
+    pub async fn upload_group_file(
+        &self,
+        file: String,
+        name: String,
+        folder: Option<String>,
+    ) -> Result<Option<String>> {
+        let group_id = self.group_id("上传群文件")?;
+        let params = api::UploadGroupFile::new(group_id, file, name, folder);
+        let data = self.call_checked(params, "上传群文件").await?;
+        Ok(data.and_then(|d| d.file_id))
+    }
+
+    /// 获取图片消息段对应的图片信息，`file` 为消息段中的 file 字段
+    pub async fn get_image(&self, file: String) -> Result<api::GetImageData> {
+        self.call_query(api::GetImage::new(file), "获取图片").await
+    }
+
+    /// 设置或取消当前群的管理员，需要 Bot 为群主
+    pub async fn set_admin(&self, user_id: i64, enable: bool) -> Result<()> {
+        let group_id = self.group_id("设置管理员")?;
+        let params = api::SetGroupAdmin::new(group_id, user_id, enable);
+        self.call_action(params, "设置管理员").await
+    }
 }
 
 impl<
@@ -163,26 +424,85 @@ impl<
     M: MessageType + fmt::Debug + Send + Sync + 'static,
 > Context<T, M>
 {
-    pub async fn finish(self) {
+    pub async fn finish(mut self) {
+        self.flush().await;
+    }
+
+    /// 本次回复实际使用的方式
+    ///
+    /// handler 指定的优先，其次为群设置；群设置为合并转发时，未附带指令原文的短回复改为普通消息。
+    fn resolve_reply_style(&self) -> ReplyStyle {
+        // 非消息事件没有可以转发或引用的原消息
+        if self.send_msg.is_none() {
+            return ReplyStyle::Plain;
+        }
+        if let Some(style) = self.reply_style {
+            return style;
+        }
+        match settings::get(self.target).reply_style {
+            ReplyStyle::Forward if !self.is_echo && is_short(&self.message_list) => {
+                ReplyStyle::Plain
+            }
+            style => style,
+        }
+    }
+
+    /// 立即发出已缓存的消息，之后缓存的消息在下一次 flush 或 finish 时发出
+    pub async fn flush(&mut self) {
         if self.message_list.is_empty() {
             return;
         }
 
-        let client = self.client;
+        match self.resolve_reply_style() {
+            ReplyStyle::Forward => {}
+            ReplyStyle::Plain => {
+                for message in std::mem::take(&mut self.message_list) {
+                    if let Err(err) = self.send_message(message).await {
+                        error!("发送消息失败: {:?}", err);
+                    }
+                }
+                return;
+            }
+            ReplyStyle::Quote => {
+                let list = std::mem::take(&mut self.message_list);
+                let message =
+                    quote_reply(self.send_msg.as_deref(), self.target, &self.sender, list);
+                if let Err(err) = self.send_message(message).await {
+                    error!("发送引用回复失败: {:?}", err);
+                }
+                return;
+            }
+        }
+
+        let client = self.client.clone();
         let target = self.target;
-        let list = self.message_list;
+        let list = std::mem::take(&mut self.message_list);
         let sender = self.sender.clone();
         let is_echo = self.is_echo;
-        let msg = self.send_msg;
+        let msg = self.send_msg.clone();
+        // 指令原文只在第一次发出时附带
+        self.is_echo = false;
 
         if let Some(msg) = msg {
             match target {
                 Target::Group(_) => {
-                    let params =
-                        api::SendGroupForwardMessageParams::new(is_echo, list, sender, msg, target);
+                    let (client, list, sender, msg) = (&client, &list, &sender, &msg);
                     match async move {
-                        let call = client.call_api(params, Echo::new()).await?;
-                        let res = call.wait_echo().await?;
+                        let res = outbound::dispatch(target, Priority::Interactive, || async {
+                            let params = api::SendGroupForwardMessageParams::new(
+                                is_echo,
+                                list.clone(),
+                                sender.clone(),
+                                msg.clone(),
+                                target,
+                            );
+                            client
+                                .call_api(params, Echo::new())
+                                .await?
+                                .wait_echo()
+                                .await
+                        })
+                        .await?;
                         trace!(?res);
                         match res.status {
                             api::Status::Ok => {}
@@ -207,12 +527,23 @@ impl<
                     }
                 }
                 Target::Private(_) => {
-                    let params = api::SendPrivateForwardMessageParams::new(
-                        is_echo, list, sender, msg, target,
-                    );
+                    let (client, list, sender, msg) = (&client, &list, &sender, &msg);
                     match async move {
-                        let call = client.call_api(params, Echo::new()).await?;
-                        let res = call.wait_echo().await?;
+                        let res = outbound::dispatch(target, Priority::Interactive, || async {
+                            let params = api::SendPrivateForwardMessageParams::new(
+                                is_echo,
+                                list.clone(),
+                                sender.clone(),
+                                msg.clone(),
+                                target,
+                            );
+                            client
+                                .call_api(params, Echo::new())
+                                .await?
+                                .wait_echo()
+                                .await
+                        })
+                        .await?;
                         trace!(?res);
                         match res.status {
                             api::Status::Ok => {}
@@ -240,3 +571,186 @@ impl<
         }
     }
 }
+
+/// 只有一条且不超过 `SHORT_REPLY_CHARS` 字的纯文本消息
+fn is_short(list: &[MessageSend]) -> bool {
+    let [message] = list else {
+        return false;
+    };
+    let segments = match message {
+        MessageSend::Array(segments) => segments.as_slice(),
+        MessageSend::Single(segment) => std::slice::from_ref(segment),
+    };
+    let mut chars = 0;
+    for segment in segments {
+        let SegmentSend::Text(text) = segment else {
+            return false;
+        };
+        chars += text.text.chars().count();
+    }
+    chars <= SHORT_REPLY_CHARS
+}
+
+/// 把所有消息合并为一条，开头引用触发的消息，群聊中同时 @ 发送者
+fn quote_reply(
+    msg: Option<&Message>,
+    target: Target,
+    sender: &Sender,
+    list: Vec<MessageSend>,
+) -> MessageSend {
+    let mut builder = MessageSend::new_message();
+    if let Some(msg) = msg {
+        let message_id = match msg {
+            Message::Private(p) => p.message_id,
+            Message::Group(g) => g.message_id,
+        };
+        builder = builder.reply(message_id.to_string());
+    }
+    if let (Target::Group(_), Some(user_id)) = (target, sender.user_id) {
+        builder = builder.at(user_id.to_string());
+    }
+    for (i, message) in list.into_iter().enumerate() {
+        if i > 0 {
+            builder = builder.text("\n");
+        }
+        builder = builder.add_msg(message);
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::{FAKE_SELF_ID, FakeBot};
+    use crate::abi::message::{Event, from_str};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_moderation() -> anyhow::Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        bot.group_message(123, 456, "/mute")?;
+        bot.private_message(456, "/mute")?;
+        let mut next = async || match subscribe.recv().await {
+            Some(Event::Message(msg)) => Arc::new(*msg),
+            _ => panic!("没有收到注入的消息"),
+        };
+        let group = Context::new(bot.clone(), next().await);
+        let private = Context::new(bot.clone(), next().await);
+
+        group.mute(789, Duration::from_secs(600)).await?;
+        group.kick(789, true).await?;
+        private.recall(42).await?;
+        assert!(private.mute(789, Duration::ZERO).await.is_err());
+
+        let calls = bot.calls();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0].action, "set_group_ban");
+        assert_eq!(
+            calls[0].params,
+            json!({ "group_id": 123, "user_id": 789, "duration": 600 })
+        );
+        assert_eq!(calls[1].action, "set_group_kick");
+        assert_eq!(calls[2].params, json!({ "message_id": 42 }));
+
+        bot.script("set_essence_msg", |_| Err("权限不足".to_string()));
+        let err = group.set_essence(42).await.unwrap_err();
+        assert!(err.to_string().starts_with("设置精华消息失败"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_query() -> anyhow::Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        bot.group_message(123, 456, "/quote")?;
+        let Some(Event::Message(msg)) = subscribe.recv().await else {
+            panic!("没有收到注入的消息");
+        };
+        let ctx = Context::new(bot.clone(), Arc::new(*msg));
+
+        bot.script("get_msg", |params| {
+            Ok(json!({
+                "message_type": "group",
+                "time": 1700000000,
+                "self_id": 10000,
+                "sub_type": "normal",
+                "message_id": params["message_id"],
+                "group_id": 123,
+                "user_id": 789,
+                "anonymous": null,
+                "raw_message": "被引用的消息",
+                "font": 0,
+                "sender": { "user_id": 789, "nickname": "Quoted", "role": "member" },
+                "message": [{ "type": "text", "data": { "text": "被引用的消息" } }],
+            }))
+        });
+        let quoted = ctx.get_msg(42).await?;
+        assert_eq!(quoted.get_text(), "被引用的消息");
+        assert_eq!(quoted.get_sender().user_id, Some(789));
+
+        bot.script("get_friend_list", |_| {
+            Ok(json!([{ "user_id": 789, "nickname": "Friend", "remark": "" }]))
+        });
+        let friends = ctx.get_friend_list().await?;
+        assert_eq!(friends.len(), 1);
+        assert_eq!(friends[0].user_id, 789);
+
+        // 未编排响应时 data 为 null
+        assert!(ctx.get_group_list().await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reply_style() -> anyhow::Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        for _ in 0..3 {
+            bot.group_message(124, 456, "/test")?;
+        }
+        let mut next = async || match subscribe.recv().await {
+            Some(Event::Message(msg)) => Arc::new(*msg),
+            _ => panic!("没有收到注入的消息"),
+        };
+
+        // 单条短回复自动改为普通消息
+        let mut ctx = Context::new(bot.clone(), next().await);
+        ctx.send_message_async(from_str("好的"));
+        ctx.finish().await;
+
+        let mut ctx = Context::new(bot.clone(), next().await);
+        ctx.send_message_async(from_str("第一条"));
+        ctx.send_message_async(from_str("第二条"));
+        ctx.finish().await;
+
+        let mut ctx = Context::new(bot.clone(), next().await);
+        let message_id = match ctx.message.as_ref() {
+            Message::Group(g) => g.message_id,
+            Message::Private(p) => p.message_id,
+        };
+        ctx.set_reply_style(ReplyStyle::Quote);
+        ctx.send_message_async(from_str("第一条"));
+        ctx.send_message_async(from_str("第二条"));
+        ctx.finish().await;
+
+        let calls = bot.calls();
+        let actions = calls.iter().map(|c| c.action).collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            ["send_group_msg", "send_group_forward_msg", "send_group_msg"]
+        );
+        assert!(calls[0].params.to_string().contains("好的"));
+        // 转发节点使用 Bot 自己的 QQ 号
+        let node = &calls[1].params["messages"][0]["data"];
+        assert_eq!(node["user_id"], FAKE_SELF_ID.to_string());
+
+        let segments = &calls[2].params["message"];
+        assert_eq!(segments[0]["type"], "reply");
+        assert_eq!(segments[0]["data"]["id"], message_id.to_string());
+        assert_eq!(segments[1]["type"], "at");
+        assert_eq!(segments[1]["data"]["qq"], "456");
+        let text = segments.to_string();
+        assert!(text.contains("第一条") && text.contains("第二条"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use crate::abi::logic_import::Message;
+use crate::abi::message::Target;
+use dashmap::DashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// 等待回复时发送该文本可以取消
+pub const CANCEL_WORD: &str = "取消";
+
+type Filter = Box<dyn Fn(&Message) -> bool + Send + Sync>;
+
+struct Waiter {
+    id: u64,
+    filter: Filter,
+    reply: oneshot::Sender<Option<Arc<Message>>>,
+}
+
+/// 正在等待回复的 handler，同一目标中的同一用户只保留最新的一个
+static WAITERS: LazyLock<DashMap<(Target, i64), Waiter>> = LazyLock::new(DashMap::new);
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// 等待回复失败的原因，`handle_error` 会把它原样回复给用户
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitError {
+    /// 用户发送了取消，或同一用户又发起了新的等待
+    Cancelled,
+    Timeout,
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitError::Cancelled => write!(f, "操作已取消"),
+            WaitError::Timeout => write!(f, "等待回复超时，操作已取消"),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+/// 登记一次等待，返回登记编号与接收回复的通道
+///
+/// 收到 `None` 表示用户取消。
+pub(crate) fn register(
+    target: Target,
+    user_id: i64,
+    filter: Filter,
+) -> (u64, oneshot::Receiver<Option<Arc<Message>>>) {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = oneshot::channel();
+    let waiter = Waiter {
+        id,
+        filter,
+        reply: tx,
+    };
+    // 旧的等待者的发送端被丢弃，对方会按取消处理
+    WAITERS.insert((target, user_id), waiter);
+    (id, rx)
+}
+
+/// 超时后移除自己的登记，不会误删之后新登记的等待者
+pub(crate) fn unregister(target: Target, user_id: i64, id: u64) {
+    WAITERS.remove_if(&(target, user_id), |_, waiter| waiter.id == id);
+}
+
+/// 停机后不会再有消息被认领，取消所有等待，handler 会收到 `WaitError::Cancelled`
+pub fn cancel_all() {
+    let count = WAITERS.len();
+    // 发送端被丢弃后等待方按取消处理
+    WAITERS.clear();
+    if count > 0 {
+        debug!("已取消 {} 个等待回复的 handler", count);
+    }
+}
+
+/// 把消息交给正在等待的 handler，被认领的消息不再进入指令与 LLM 分发
+pub fn claim(target: Target, user_id: Option<i64>, message: &Arc<Message>, text: &str) -> bool {
+    let Some(user_id) = user_id else {
+        return false;
+    };
+    let cancel = text.trim() == CANCEL_WORD;
+    let Some((_, waiter)) = WAITERS.remove_if(&(target, user_id), |_, waiter| {
+        cancel || (waiter.filter)(message)
+    }) else {
+        return false;
+    };
+
+    debug!(
+        "用户 {} 在 {:?} 中的消息被等待中的 handler 认领",
+        user_id, target
+    );
+    let reply = if cancel { None } else { Some(message.clone()) };
+    // handler 已经超时退出时发送失败，消息同样视为已认领
+    let _ = waiter.reply.send(reply);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{
+        Context,
+        fake::FakeBot,
+        message::{Event, MessageType, from_str},
+    };
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn next_message(subscribe: &mut mpsc::UnboundedReceiver<Event>) -> Arc<Message> {
+        let Some(Event::Message(msg)) = subscribe.recv().await else {
+            panic!("没有收到注入的消息");
+        };
+        Arc::new(*msg)
+    }
+
+    /// 与路由循环中的认领逻辑一致
+    fn route(message: &Arc<Message>) -> bool {
+        claim(
+            message.get_target(),
+            message.get_sender().user_id,
+            message,
+            &message.get_text(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_next_message() -> anyhow::Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        bot.group_message(2001, 456, "/download 课程")?;
+        let msg = next_message(&mut subscribe).await;
+
+        let mut ctx = Context::new(bot.clone(), msg);
+        ctx.send_message_async(from_str("请回复序号"));
+        let waiting = tokio::spawn(async move {
+            ctx.wait_for_next_message(|msg| msg.get_text().parse::<usize>().is_ok(), TIMEOUT)
+                .await
+                .map(|msg| msg.get_text())
+        });
+
+        // 提问在等待开始前发出，短消息不使用合并转发
+        bot.wait_for("send_group_msg", TIMEOUT)
+            .await
+            .expect("没有先发出提问");
+
+        bot.group_message(2001, 789, "1")?;
+        assert!(!route(&next_message(&mut subscribe).await));
+        bot.group_message(2001, 456, "不是序号")?;
+        assert!(!route(&next_message(&mut subscribe).await));
+        bot.group_message(2001, 456, "2")?;
+        assert!(route(&next_message(&mut subscribe).await));
+
+        assert_eq!(waiting.await??, "2");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_timeout() -> anyhow::Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        bot.group_message(2002, 456, "/download 课程")?;
+        let msg = next_message(&mut subscribe).await;
+
+        let mut ctx = Context::new(bot.clone(), msg);
+        let err = ctx
+            .wait_for_next_message(|_| true, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<WaitError>(), Some(&WaitError::Timeout));
+        assert!(WAITERS.get(&(Target::Group(2002), 456)).is_none());
+
+        let waiting = tokio::spawn(async move {
+            ctx.wait_for_next_message(|_| false, TIMEOUT).await
+        });
+        while WAITERS.get(&(Target::Group(2002), 456)).is_none() {
+            tokio::task::yield_now().await;
+        }
+        // 取消不受过滤条件限制
+        bot.group_message(2002, 456, CANCEL_WORD)?;
+        assert!(route(&next_message(&mut subscribe).await));
+
+        let err = waiting.await?.unwrap_err();
+        assert_eq!(err.downcast_ref::<WaitError>(), Some(&WaitError::Cancelled));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_router_claim() -> anyhow::Result<()> {
+        let bot = FakeBot::spawn_router();
+        let (_, reply) = register(Target::Group(2003), 456, Box::new(|_| true));
+
+        bot.group_message(2003, 456, "/echo 你好")?;
+        let claimed = tokio::time::timeout(TIMEOUT, reply).await??;
+        assert_eq!(claimed.unwrap().get_text(), "/echo 你好");
+
+        // 被认领的指令不会再触发 echo
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(bot.calls().is_empty());
+
+        Ok(())
+    }
+}
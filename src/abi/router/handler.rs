@@ -4,9 +4,16 @@ use crate::{
             Event, MessageType, Type, event_body::message_sent::MessageSent, event_meta::MetaEvent,
         },
         network::BotClient,
-        router::context::Context,
-        websocket::{BotHandler, BotWebsocketClient},
+        router::{
+            context::Context,
+            conversation,
+            limit::RateLimit,
+            middleware::{Flow, Middleware},
+            permission::Permission,
+        },
+        websocket::{BotHandler, BotTransport},
     },
+    api::llm::chat::router::handle_llm_message_sent,
     logic::dispatch_all_handlers,
 };
 use anyhow::Result;
@@ -22,6 +29,8 @@ where
 {
     const FILTER_TYPE: Option<Type>;
     const FILTER_CMD: Option<&'static str>;
+    const PERMISSION: Permission;
+    const LIMIT: Option<RateLimit>;
 
     fn handle(&self, context: &Context<T, M>) -> Result<()>;
 }
@@ -31,64 +40,91 @@ pub trait Router<T>
 where
     T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
 {
-    fn new(subscribe: mpsc::UnboundedReceiver<Event>, client: BotWebsocketClient<T>) -> Self;
+    fn new(subscribe: mpsc::UnboundedReceiver<Event>, transport: Box<dyn BotTransport<T>>) -> Self;
     fn get_client(&self) -> Arc<T>;
+    /// 处理事件直到连接关闭或收到停机信号
     async fn run(&mut self) -> ();
+    /// 停机时断开与 Napcat 的连接
+    async fn close(&mut self);
 }
 
-pub trait SpawnContext<T, R>
-where
-    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
-    R: Router<T>,
-{
-    fn spawn_context<M: MessageType + fmt::Debug + Send + Sync + 'static>(&self, msg: Arc<M>);
+pub struct NapcatRouter<T: BotHandler> {
+    subscribe: mpsc::UnboundedReceiver<Event>,
+    transport: Box<dyn BotTransport<T>>,
+    middlewares: Vec<Box<dyn Middleware<T>>>,
 }
 
-impl<T, R> SpawnContext<T, R> for R
-where
-    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
-    R: Router<T>,
-{
-    fn spawn_context<M: MessageType + fmt::Debug + Send + Sync + 'static>(&self, msg: Arc<M>) {
-        let client_arc = self.get_client();
-        let context = Context::new(client_arc, msg);
-
-        dispatch_all_handlers(context);
+impl<T: BotHandler + BotClient + fmt::Debug> NapcatRouter<T> {
+    /// 追加一个中间件，按添加顺序执行
+    pub fn add_middleware(&mut self, middleware: impl Middleware<T> + 'static) -> &mut Self {
+        self.middlewares.push(Box::new(middleware));
+        self
     }
 }
 
-pub struct NapcatRouter<T: BotHandler> {
-    subscribe: mpsc::UnboundedReceiver<Event>,
-    client: BotWebsocketClient<T>,
+/// 依次执行中间件，任意一个返回 `Stop` 时跳过本次事件
+macro_rules! run_middlewares {
+    ($self:ident, $method:ident, $ctx:ident) => {
+        let mut stopped = false;
+        for middleware in &$self.middlewares {
+            if middleware.$method(&mut $ctx).await == Flow::Stop {
+                stopped = true;
+                break;
+            }
+        }
+        if stopped {
+            continue;
+        }
+    };
 }
 
 #[async_trait]
 impl<T: BotHandler + BotClient + fmt::Debug> Router<T> for NapcatRouter<T> {
-    fn new(subscribe: mpsc::UnboundedReceiver<Event>, client: BotWebsocketClient<T>) -> Self {
-        NapcatRouter { subscribe, client }
+    fn new(subscribe: mpsc::UnboundedReceiver<Event>, transport: Box<dyn BotTransport<T>>) -> Self {
+        NapcatRouter {
+            subscribe,
+            transport,
+            middlewares: Vec::new(),
+        }
     }
 
     fn get_client(&self) -> Arc<T> {
-        self.client.handler.clone()
+        self.transport.handler()
     }
 
     async fn run(&mut self) {
-        while let Some(event) = self.subscribe.recv().await {
+        let shutdown = crate::shutdown::global();
+        while let Some(event) = tokio::select! {
+            biased;
+            _ = shutdown.triggered() => None,
+            event = self.subscribe.recv() => event,
+        } {
             match event {
                 Event::Message(msg) => {
                     debug!("处理消息事件: {:?}", msg);
-                    let ctx_data = Arc::new(*msg);
-                    self.spawn_context(ctx_data);
+                    let mut ctx = Context::new(self.get_client(), Arc::new(*msg));
+                    run_middlewares!(self, on_message, ctx);
+                    if conversation::claim(
+                        ctx.target,
+                        ctx.sender.user_id,
+                        &ctx.message,
+                        ctx.get_message_text(),
+                    ) {
+                        continue;
+                    }
+                    dispatch_all_handlers(ctx);
                 }
                 Event::Notice(notice) => {
                     debug!("处理通知事件: {:?}", notice);
-                    let ctx_data = Arc::new(notice);
-                    self.spawn_context(ctx_data);
+                    let mut ctx = Context::new(self.get_client(), Arc::new(notice));
+                    run_middlewares!(self, on_notice, ctx);
+                    dispatch_all_handlers(ctx);
                 }
                 Event::Request(req) => {
                     debug!("处理请求事件: {:?}", req);
-                    let ctx_data = Arc::new(req);
-                    self.spawn_context(ctx_data);
+                    let mut ctx = Context::new(self.get_client(), Arc::new(req));
+                    run_middlewares!(self, on_request, ctx);
+                    dispatch_all_handlers(ctx);
                 }
                 Event::MetaEvent(meta) => {
                     debug!("处理元事件: {:?}", meta);
@@ -100,12 +136,15 @@ impl<T: BotHandler + BotClient + fmt::Debug> Router<T> for NapcatRouter<T> {
                         MetaEvent::Lifecycle(lc) => {
                             trace!("收到生命周期事件: {:?}", lc);
                         }
+                        MetaEvent::Unknown(value) => {
+                            trace!("收到未知元事件: {}", value);
+                        }
                     }
                 }
                 Event::MessageSent(message_sent) => {
                     debug!("处理消息发送事件: {:?}", message_sent);
 
-                    match *message_sent {
+                    match message_sent.as_ref() {
                         MessageSent::Private(p) => {
                             trace!("私人消息已发送: {:?}", p);
                         }
@@ -113,8 +152,20 @@ impl<T: BotHandler + BotClient + fmt::Debug> Router<T> for NapcatRouter<T> {
                             trace!("群消息已发送: {:?}", g);
                         }
                     }
+                    let client = self.get_client();
+                    shutdown.spawn(async move {
+                        handle_llm_message_sent(&message_sent, client.as_ref()).await;
+                    });
                 }
             }
         }
+        // 不再分发事件后等待中的 handler 收不到回复，取消它们以免拖住停机
+        if shutdown.is_triggered() {
+            conversation::cancel_all();
+        }
+    }
+
+    async fn close(&mut self) {
+        self.transport.close().await;
     }
 }
@@ -0,0 +1,160 @@
+use crate::abi::message::{Sender, Target};
+use crate::abi::router::permission::is_bot_admin;
+use crate::api::storage::HotTable;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+/// 按北京时间 (UTC+8) 划分自然日
+const DAY_OFFSET_SECS: i64 = 8 * 3600;
+const DAY_SECS: i64 = 24 * 3600;
+
+static USAGE: LazyLock<HotTable<UsageKey, Usage>> =
+    LazyLock::new(|| HotTable::new("command_usage"));
+
+/// 限流的统计范围
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitScope {
+    User,
+    /// 群内所有成员共享，私聊中按用户计算
+    Group,
+}
+
+/// 指令的冷却与每日次数限制，由 `#[handler(cooldown = ..., daily_quota = ..., scope = ...)]` 生成
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub name: &'static str,
+    pub cooldown_secs: i64,
+    pub daily_quota: Option<u32>,
+    pub scope: LimitScope,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+struct UsageKey {
+    name: String,
+    scope: LimitScope,
+    id: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct Usage {
+    /// 上次调用的 Unix 秒
+    last: i64,
+    /// 计数所属的自然日编号
+    day: i64,
+    count: u32,
+}
+
+impl RateLimit {
+    /// 尝试占用一次调用额度，被拒绝时返回给用户的提示
+    ///
+    /// 路由循环中串行调用，检查与更新之间不会有并发。
+    pub fn acquire(&self, sender: &Sender, target: Target) -> Result<(), String> {
+        let Some(user_id) = sender.user_id else {
+            return Ok(());
+        };
+        if is_bot_admin(user_id) {
+            return Ok(());
+        }
+
+        let id = match (self.scope, target) {
+            (LimitScope::Group, Target::Group(group_id)) => group_id,
+            _ => user_id,
+        };
+        let key = UsageKey {
+            name: self.name.to_string(),
+            scope: self.scope,
+            id,
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let usage = self.next_usage(USAGE.get(&key).as_deref(), now)?;
+        if let Err(e) = USAGE.insert(key, Arc::new(usage)) {
+            error!("保存指令调用记录失败: {:?}", e);
+        }
+
+        Ok(())
+    }
+
+    fn next_usage(&self, usage: Option<&Usage>, now: i64) -> Result<Usage, String> {
+        let day = (now + DAY_OFFSET_SECS).div_euclid(DAY_SECS);
+        let Some(usage) = usage else {
+            return Ok(Usage {
+                last: now,
+                day,
+                count: 1,
+            });
+        };
+
+        let wait = usage.last + self.cooldown_secs - now;
+        if wait > 0 {
+            return Err(format!("指令冷却中，请在 {} 后再试", format_duration(wait)));
+        }
+
+        let count = if usage.day == day { usage.count } else { 0 };
+        if let Some(quota) = self.daily_quota
+            && count >= quota
+        {
+            let reset = (day + 1) * DAY_SECS - DAY_OFFSET_SECS - now;
+            return Err(format!(
+                "今日次数已用完 ({}/{})，将在 {} 后重置",
+                count,
+                quota,
+                format_duration(reset)
+            ));
+        }
+
+        Ok(Usage {
+            last: now,
+            day,
+            count: count + 1,
+        })
+    }
+}
+
+fn format_duration(secs: i64) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+    match (h, m) {
+        (0, 0) => format!("{} 秒", s),
+        (0, _) => format!("{} 分 {} 秒", m, s),
+        _ => format!("{} 小时 {} 分", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: RateLimit = RateLimit {
+        name: "test",
+        cooldown_secs: 30,
+        daily_quota: Some(2),
+        scope: LimitScope::User,
+    };
+
+    #[test]
+    fn test_next_usage() {
+        // 北京时间 2023-11-15 06:13:20
+        let now = 1700000000;
+        let first = LIMIT.next_usage(None, now).unwrap();
+        assert_eq!(first.count, 1);
+
+        let err = LIMIT.next_usage(Some(&first), now + 10).unwrap_err();
+        assert!(err.contains("20 秒"));
+
+        let second = LIMIT.next_usage(Some(&first), now + 30).unwrap();
+        assert_eq!(second.count, 2);
+
+        let err = LIMIT.next_usage(Some(&second), now + 60).unwrap_err();
+        assert!(err.contains("(2/2)"));
+        assert!(err.contains("17 小时 45 分"));
+
+        // 第二天重新计数
+        let next_day = LIMIT.next_usage(Some(&second), now + DAY_SECS).unwrap();
+        assert_eq!(next_day.count, 1);
+    }
+}
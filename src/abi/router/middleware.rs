@@ -0,0 +1,325 @@
+use crate::abi::router::permission::is_bot_admin;
+use crate::abi::{
+    Context,
+    logic_import::{Message, Notice, Request},
+    message::{MessageType, Target},
+    network::BotClient,
+    websocket::BotHandler,
+};
+use crate::config::{self, BotConfig};
+use async_trait::async_trait;
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tracing::{debug, info_span};
+
+/// 中间件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// 交给下一个中间件，最终进入 handler 分发
+    Continue,
+    /// 丢弃该事件，后续中间件与 handler 都不会执行
+    Stop,
+}
+
+/// 在 handler 分发之前按注册顺序执行的拦截器
+///
+/// 中间件在路由循环中直接 await，不会额外 `tokio::spawn`，
+/// 因此实现中不要做耗时的网络请求，需要时自行 spawn。
+#[async_trait]
+pub trait Middleware<T>: Send + Sync
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    async fn on_message(&self, _ctx: &mut Context<T, Message>) -> Flow {
+        Flow::Continue
+    }
+
+    async fn on_notice(&self, _ctx: &mut Context<T, Notice>) -> Flow {
+        Flow::Continue
+    }
+
+    async fn on_request(&self, _ctx: &mut Context<T, Request>) -> Flow {
+        Flow::Continue
+    }
+}
+
+fn sender_id<T, M>(ctx: &Context<T, M>) -> Option<i64>
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+    M: MessageType + fmt::Debug + Send + Sync + 'static,
+{
+    ctx.sender.user_id
+}
+
+/// 中间件使用的 QQ 号名单
+#[derive(Debug)]
+enum IdList {
+    Fixed(HashSet<i64>),
+    /// 每次从当前配置中读取，配置热重载后立即生效；返回 None 表示未配置该名单
+    Config(fn(&BotConfig) -> Option<&[i64]>),
+}
+
+impl IdList {
+    fn fixed(ids: impl IntoIterator<Item = i64>) -> Self {
+        IdList::Fixed(ids.into_iter().collect())
+    }
+
+    fn contains(&self, id: i64) -> Option<bool> {
+        match self {
+            IdList::Fixed(ids) => Some(ids.contains(&id)),
+            IdList::Config(get) => get(&config::current().bot).map(|ids| ids.contains(&id)),
+        }
+    }
+}
+
+/// 全局用户黑名单，名单内用户的消息一律丢弃
+///
+/// 好友与加群请求不在此拦截，由请求 handler 自动拒绝。
+#[derive(Debug)]
+pub struct Blacklist {
+    users: IdList,
+}
+
+impl Blacklist {
+    pub fn new(users: impl IntoIterator<Item = i64>) -> Self {
+        Blacklist {
+            users: IdList::fixed(users),
+        }
+    }
+
+    /// 使用配置中的 bot.blacklist
+    pub fn from_config() -> Self {
+        Blacklist {
+            users: IdList::Config(|bot| Some(&bot.blacklist)),
+        }
+    }
+
+    fn check(&self, user_id: Option<i64>) -> Flow {
+        match user_id {
+            Some(id) if self.users.contains(id) == Some(true) => {
+                debug!("忽略黑名单用户 {} 的事件", id);
+                Flow::Stop
+            }
+            _ => Flow::Continue,
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Middleware<T> for Blacklist
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    async fn on_message(&self, ctx: &mut Context<T, Message>) -> Flow {
+        self.check(sender_id(ctx))
+    }
+}
+
+/// 群白名单，只处理名单内群的消息与通知，私聊不受影响
+#[derive(Debug)]
+pub struct GroupAllowList {
+    groups: IdList,
+}
+
+impl GroupAllowList {
+    pub fn new(groups: impl IntoIterator<Item = i64>) -> Self {
+        GroupAllowList {
+            groups: IdList::fixed(groups),
+        }
+    }
+
+    /// 使用配置中的 bot.group_allowlist，未配置时处理所有群
+    pub fn from_config() -> Self {
+        GroupAllowList {
+            groups: IdList::Config(|bot| bot.group_allowlist.as_deref()),
+        }
+    }
+
+    fn check(&self, target: Target) -> Flow {
+        match target {
+            Target::Group(group_id) if self.groups.contains(group_id) == Some(false) => {
+                debug!("忽略未在白名单中的群 {}", group_id);
+                Flow::Stop
+            }
+            _ => Flow::Continue,
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Middleware<T> for GroupAllowList
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    async fn on_message(&self, ctx: &mut Context<T, Message>) -> Flow {
+        self.check(ctx.target)
+    }
+
+    async fn on_notice(&self, ctx: &mut Context<T, Notice>) -> Flow {
+        self.check(ctx.target)
+    }
+}
+
+/// 忽略其他 Bot 与自身发出的消息，避免互相触发指令
+#[derive(Debug)]
+pub struct IgnoreBots {
+    bots: IdList,
+}
+
+impl IgnoreBots {
+    pub fn new(bots: impl IntoIterator<Item = i64>) -> Self {
+        IgnoreBots {
+            bots: IdList::fixed(bots),
+        }
+    }
+
+    /// 使用配置中的 bot.ignore_bots
+    pub fn from_config() -> Self {
+        IgnoreBots {
+            bots: IdList::Config(|bot| Some(&bot.ignore_bots)),
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Middleware<T> for IgnoreBots
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    async fn on_message(&self, ctx: &mut Context<T, Message>) -> Flow {
+        let self_id = match ctx.message.as_ref() {
+            Message::Private(p) => p.self_id,
+            Message::Group(g) => g.self_id,
+        };
+        match sender_id(ctx) {
+            Some(id) if id == self_id || self.bots.contains(id) == Some(true) => {
+                debug!("忽略 Bot {} 的消息", id);
+                Flow::Stop
+            }
+            _ => Flow::Continue,
+        }
+    }
+}
+
+static MAINTENANCE: AtomicBool = AtomicBool::new(false);
+
+/// 开启或关闭维护模式
+pub fn set_maintenance(on: bool) {
+    MAINTENANCE.store(on, Ordering::Relaxed);
+}
+
+pub fn is_maintenance() -> bool {
+    MAINTENANCE.load(Ordering::Relaxed)
+}
+
+/// 维护模式下丢弃除 Bot 管理员以外的所有事件
+#[derive(Debug, Default)]
+pub struct Maintenance;
+
+impl Maintenance {
+    fn check(&self, user_id: Option<i64>) -> Flow {
+        if is_maintenance() && !user_id.is_some_and(is_bot_admin) {
+            Flow::Stop
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Middleware<T> for Maintenance
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    async fn on_message(&self, ctx: &mut Context<T, Message>) -> Flow {
+        self.check(sender_id(ctx))
+    }
+
+    async fn on_notice(&self, _ctx: &mut Context<T, Notice>) -> Flow {
+        self.check(None)
+    }
+
+    async fn on_request(&self, _ctx: &mut Context<T, Request>) -> Flow {
+        self.check(None)
+    }
+}
+
+/// 为每个事件创建 tracing span，handler 任务会在该 span 下执行
+#[derive(Debug, Default)]
+pub struct TraceSpan;
+
+#[async_trait]
+impl<T> Middleware<T> for TraceSpan
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    async fn on_message(&self, ctx: &mut Context<T, Message>) -> Flow {
+        let message_id = match ctx.message.as_ref() {
+            Message::Private(p) => p.message_id,
+            Message::Group(g) => g.message_id,
+        };
+        ctx.span = info_span!("message", id = message_id, target = ?ctx.target, user = ?ctx.sender.user_id);
+        Flow::Continue
+    }
+
+    async fn on_notice(&self, ctx: &mut Context<T, Notice>) -> Flow {
+        ctx.span = info_span!("notice", target = ?ctx.target, user = ?ctx.sender.user_id);
+        Flow::Continue
+    }
+
+    async fn on_request(&self, ctx: &mut Context<T, Request>) -> Flow {
+        ctx.span = info_span!("request", target = ?ctx.target, user = ?ctx.sender.user_id);
+        Flow::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::FakeBot;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_blacklist() -> anyhow::Result<()> {
+        let (bot, mut subscribe) = FakeBot::new();
+        bot.group_message(123, 456, "/echo 你好")?;
+        let Some(crate::abi::message::Event::Message(msg)) = subscribe.recv().await else {
+            panic!("没有收到注入的消息");
+        };
+
+        let mut ctx = Context::new(bot.clone(), std::sync::Arc::new(*msg));
+        assert_eq!(Blacklist::new([456]).on_message(&mut ctx).await, Flow::Stop);
+        assert_eq!(
+            Blacklist::new([789]).on_message(&mut ctx).await,
+            Flow::Continue
+        );
+        assert_eq!(
+            GroupAllowList::new([321]).on_message(&mut ctx).await,
+            Flow::Stop
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_router_stop() -> anyhow::Result<()> {
+        let bot = FakeBot::spawn_router_with(|router| {
+            router.add_middleware(Blacklist::new([456]));
+        });
+        bot.group_message(123, 456, "/echo 你好")?;
+        bot.group_message(123, 789, "/echo 你好")?;
+
+        let call = bot
+            .wait_for("send_group_forward_msg", Duration::from_secs(5))
+            .await
+            .expect("没有收到转发消息");
+        assert!(call.params.to_string().contains("789"));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(bot.calls().len(), 1);
+
+        Ok(())
+    }
+}
@@ -1,2 +1,10 @@
+pub mod args;
+pub mod command;
 pub mod context;
+pub mod conversation;
 pub mod handler;
+pub mod limit;
+pub mod middleware;
+pub mod outbound;
+pub mod permission;
+pub mod settings;
@@ -0,0 +1,615 @@
+use crate::abi::message::{
+    MessageSend, Target, api,
+    message_body::{SegmentSend, text},
+};
+use crate::config::{self, OutboundConfig};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::{self, Instant};
+use tracing::{debug, warn};
+
+/// 目标的令牌桶超过该数量时清理已经回满的桶
+const MAX_IDLE_BUCKETS: usize = 1024;
+/// 排队超过该时间时记录日志
+const SLOW_WAIT: Duration = Duration::from_secs(3);
+
+static LIMITER: LazyLock<Mutex<Limiter>> =
+    LazyLock::new(|| Mutex::new(Limiter::new(Instant::now())));
+
+static QUEUES: LazyLock<Mutex<HashMap<Target, TargetQueue>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_TICKET: AtomicU64 = AtomicU64::new(0);
+
+static METRICS: Metrics = Metrics::new();
+
+/// 发送的优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// 对用户指令的回复，排队时优先发送
+    Interactive,
+    /// 定时任务、转交管理员等主动发送的消息
+    Bulk,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// 新建的桶是满的，第一次补充时按突发数截断
+    fn new(now: Instant) -> Self {
+        Bucket {
+            tokens: f64::INFINITY,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.updated = now;
+    }
+
+    /// 攒够 `need` 个令牌还需等待的时间
+    fn wait(&self, rate: f64, need: f64) -> Duration {
+        if self.tokens >= need {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((need - self.tokens) / rate)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Limiter {
+    global: Bucket,
+    targets: HashMap<Target, Bucket>,
+}
+
+impl Limiter {
+    fn new(now: Instant) -> Self {
+        Limiter {
+            global: Bucket::new(now),
+            targets: HashMap::new(),
+        }
+    }
+
+    /// 同时从全局与目标的桶中取出一个令牌，不足时返回需要等待的时间
+    ///
+    /// `reserve` 为需要给优先级更高的消息留下的全局令牌数。
+    fn try_take(
+        &mut self,
+        target: Target,
+        config: &OutboundConfig,
+        reserve: f64,
+        now: Instant,
+    ) -> Option<Duration> {
+        if self.targets.len() > MAX_IDLE_BUCKETS {
+            self.targets.retain(|_, bucket| {
+                bucket.refill(config.target_rate, config.target_burst, now);
+                bucket.tokens < config.target_burst
+            });
+        }
+
+        self.global
+            .refill(config.global_rate, config.global_burst, now);
+        let bucket = self
+            .targets
+            .entry(target)
+            .or_insert_with(|| Bucket::new(now));
+        bucket.refill(config.target_rate, config.target_burst, now);
+
+        let wait = self
+            .global
+            .wait(config.global_rate, 1.0 + reserve)
+            .max(bucket.wait(config.target_rate, 1.0));
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+        self.global.tokens -= 1.0;
+        bucket.tokens -= 1.0;
+        None
+    }
+}
+
+/// 发往同一目标的消息队列，指令回复排在主动发送的消息之前，同一优先级内先到先发
+#[derive(Debug, Default)]
+struct TargetQueue {
+    interactive: VecDeque<u64>,
+    bulk: VecDeque<u64>,
+    /// 队首变化时唤醒排队中的发送
+    notify: Arc<Notify>,
+}
+
+impl TargetQueue {
+    fn lane(&mut self, priority: Priority) -> &mut VecDeque<u64> {
+        match priority {
+            Priority::Interactive => &mut self.interactive,
+            Priority::Bulk => &mut self.bulk,
+        }
+    }
+
+    /// 下一个可以取令牌的发送
+    fn head(&self) -> Option<u64> {
+        self.interactive.front().or(self.bulk.front()).copied()
+    }
+}
+
+/// 在目标队列中的位置，发送完成或被取消时离开队列并唤醒后面的发送
+struct Ticket {
+    id: u64,
+    target: Target,
+    priority: Priority,
+    notify: Arc<Notify>,
+}
+
+impl Ticket {
+    fn new(target: Target, priority: Priority) -> Self {
+        let id = NEXT_TICKET.fetch_add(1, Ordering::Relaxed);
+        let mut queues = QUEUES.lock().unwrap();
+        let queue = queues.entry(target).or_default();
+        queue.lane(priority).push_back(id);
+        // 指令回复可能插到了正在等待的主动消息前面
+        queue.notify.notify_waiters();
+        Ticket {
+            id,
+            target,
+            priority,
+            notify: queue.notify.clone(),
+        }
+    }
+
+    /// 排到队首时尝试取令牌，返回 `None` 表示还没有轮到
+    fn try_take(&self) -> Option<Option<Duration>> {
+        let queues = QUEUES.lock().unwrap();
+        if queues.get(&self.target).and_then(TargetQueue::head) != Some(self.id) {
+            return None;
+        }
+        // 主动发送的消息要给排队中的指令回复留出全局令牌
+        let reserve = match self.priority {
+            Priority::Interactive => 0.0,
+            Priority::Bulk => METRICS.queued_interactive.load(Ordering::Relaxed) as f64,
+        };
+        let config = config::current();
+        let mut limiter = LIMITER.lock().unwrap();
+        Some(limiter.try_take(self.target, &config.outbound, reserve, Instant::now()))
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        let mut queues = QUEUES.lock().unwrap();
+        let Some(queue) = queues.get_mut(&self.target) else {
+            return;
+        };
+        let id = self.id;
+        queue.lane(self.priority).retain(|ticket| *ticket != id);
+        if queue.interactive.is_empty() && queue.bulk.is_empty() {
+            queues.remove(&self.target);
+        } else {
+            queue.notify.notify_waiters();
+        }
+    }
+}
+
+struct Metrics {
+    queued_interactive: AtomicUsize,
+    queued_bulk: AtomicUsize,
+    attempts: AtomicU64,
+    wait_ms: AtomicU64,
+    sent: AtomicU64,
+    retries: AtomicU64,
+    latency_ms: AtomicU64,
+    max_latency_ms: AtomicU64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Metrics {
+            queued_interactive: AtomicUsize::new(0),
+            queued_bulk: AtomicUsize::new(0),
+            attempts: AtomicU64::new(0),
+            wait_ms: AtomicU64::new(0),
+            sent: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            latency_ms: AtomicU64::new(0),
+            max_latency_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn queued(&self, priority: Priority) -> &AtomicUsize {
+        match priority {
+            Priority::Interactive => &self.queued_interactive,
+            Priority::Bulk => &self.queued_bulk,
+        }
+    }
+}
+
+/// 排队期间计入队列长度
+struct Queued(&'static AtomicUsize);
+
+impl Queued {
+    fn new(priority: Priority) -> Self {
+        let counter = METRICS.queued(priority);
+        counter.fetch_add(1, Ordering::Relaxed);
+        Queued(counter)
+    }
+}
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 发送队列的统计信息
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutboundStats {
+    pub queued_interactive: usize,
+    pub queued_bulk: usize,
+    pub sent: u64,
+    pub retries: u64,
+    /// 每次发送在队列中的平均等待时间
+    pub avg_wait_ms: u64,
+    /// 从进入队列到收到最终响应（包括重试）的平均耗时
+    pub avg_latency_ms: u64,
+    pub max_latency_ms: u64,
+}
+
+pub fn stats() -> OutboundStats {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let sent = load(&METRICS.sent);
+    OutboundStats {
+        queued_interactive: METRICS.queued_interactive.load(Ordering::Relaxed),
+        queued_bulk: METRICS.queued_bulk.load(Ordering::Relaxed),
+        sent,
+        retries: load(&METRICS.retries),
+        avg_wait_ms: load(&METRICS.wait_ms) / load(&METRICS.attempts).max(1),
+        avg_latency_ms: load(&METRICS.latency_ms) / sent.max(1),
+        max_latency_ms: load(&METRICS.max_latency_ms),
+    }
+}
+
+/// 等待直到可以向 `target` 发送一条消息，返回排队的时间
+async fn acquire(target: Target, priority: Priority) -> Duration {
+    let start = Instant::now();
+    let _queued = Queued::new(priority);
+    let ticket = Ticket::new(target, priority);
+    loop {
+        // 先登记唤醒再检查队列，避免错过检查之后的唤醒
+        let notified = ticket.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        match ticket.try_take() {
+            Some(None) => break,
+            Some(Some(wait)) => {
+                tokio::select! {
+                    _ = time::sleep(wait) => {}
+                    _ = notified => {}
+                }
+            }
+            None => notified.await,
+        }
+    }
+    start.elapsed()
+}
+
+/// 响应表示被限流且还能重试时返回重试前等待的毫秒数
+fn retry_backoff<D: api::Data>(
+    config: &OutboundConfig,
+    res: &api::ApiResponse<D>,
+    attempt: u32,
+) -> Option<u64> {
+    let limited = matches!(res.status, api::Status::Failed)
+        && config.rate_limited_retcodes.contains(&res.retcode);
+    (limited && attempt < config.max_retries)
+        .then(|| config.retry_base_ms.saturating_mul(1 << attempt.min(16)))
+}
+
+/// 经过限流执行一次发送，被限流时退避重试，`call` 在每次重试时重新执行
+pub async fn dispatch<D, F, Fut>(
+    target: Target,
+    priority: Priority,
+    call: F,
+) -> Result<api::ApiResponse<D>>
+where
+    D: api::Data,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<api::ApiResponse<D>>>,
+{
+    let start = Instant::now();
+    let mut attempt = 0;
+    loop {
+        let waited = acquire(target, priority).await;
+        METRICS.attempts.fetch_add(1, Ordering::Relaxed);
+        METRICS
+            .wait_ms
+            .fetch_add(waited.as_millis() as u64, Ordering::Relaxed);
+        if waited >= SLOW_WAIT {
+            debug!("发往 {:?} 的消息排队了 {:?}", target, waited);
+        }
+
+        let res = call().await;
+        let backoff = match &res {
+            Ok(res) => retry_backoff(&config::current().outbound, res, attempt),
+            Err(_) => None,
+        };
+        if let Some(backoff) = backoff {
+            attempt += 1;
+            METRICS.retries.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "发往 {:?} 的消息被限流，{} 毫秒后进行第 {} 次重试",
+                target, backoff, attempt
+            );
+            time::sleep(Duration::from_millis(backoff)).await;
+            continue;
+        }
+
+        let latency = start.elapsed().as_millis() as u64;
+        METRICS.sent.fetch_add(1, Ordering::Relaxed);
+        METRICS.latency_ms.fetch_add(latency, Ordering::Relaxed);
+        METRICS.max_latency_ms.fetch_max(latency, Ordering::Relaxed);
+        return res;
+    }
+}
+
+fn text_chars(segments: &[SegmentSend]) -> usize {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            SegmentSend::Text(text) => text.text.chars().count(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// 在不超过 `max_chars` 个字符的位置切分，切出的前半段不少于一半时优先在换行处切分
+fn split_text(text: &str, max_chars: usize) -> (&str, &str) {
+    let end = text
+        .char_indices()
+        .nth(max_chars)
+        .map_or(text.len(), |(i, _)| i);
+    let end = match text[..end].rfind('\n') {
+        Some(pos) if pos + 1 >= end / 2 => pos + 1,
+        _ => end,
+    };
+    text.split_at(end)
+}
+
+/// 按文本字数把消息拆分为多条，非文本段不计字数也不会被拆开
+pub fn split(message: MessageSend, max_chars: usize) -> Vec<MessageSend> {
+    let segments = match message {
+        MessageSend::Array(segments) => segments,
+        MessageSend::Single(segment) => vec![segment],
+    };
+    if text_chars(&segments) <= max_chars {
+        return vec![MessageSend::Array(segments)];
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = Vec::new();
+    let mut count = 0;
+    for segment in segments {
+        let SegmentSend::Text(data) = segment else {
+            current.push(segment);
+            continue;
+        };
+        let mut rest = data.text.as_str();
+        loop {
+            let len = rest.chars().count();
+            if count + len <= max_chars {
+                if !rest.is_empty() {
+                    current.push(SegmentSend::Text(text::DataSend {
+                        text: rest.to_string(),
+                    }));
+                }
+                count += len;
+                break;
+            }
+            let (head, tail) = split_text(rest, max_chars - count);
+            if !head.is_empty() {
+                current.push(SegmentSend::Text(text::DataSend {
+                    text: head.to_string(),
+                }));
+            }
+            chunks.push(MessageSend::Array(std::mem::take(&mut current)));
+            count = 0;
+            rest = tail;
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(MessageSend::Array(current));
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::FakeBot;
+    use crate::abi::message::from_str;
+    use crate::abi::router::context::send_message_to;
+    use std::sync::atomic::AtomicU32;
+
+    fn texts(chunks: &[MessageSend]) -> Vec<String> {
+        chunks
+            .iter()
+            .map(|chunk| match chunk {
+                MessageSend::Array(segments) => segments
+                    .iter()
+                    .filter_map(|segment| match segment {
+                        SegmentSend::Text(text) => Some(text.text.as_str()),
+                        _ => None,
+                    })
+                    .collect(),
+                MessageSend::Single(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split(from_str("你好"), 10).len(), 1);
+        assert_eq!(
+            texts(&split(from_str("一二三四五六七"), 3)),
+            ["一二三", "四五六", "七"]
+        );
+        // 优先在换行处切分
+        assert_eq!(
+            texts(&split(from_str("第一行内容\n第二行"), 8)),
+            ["第一行内容\n", "第二行"]
+        );
+
+        let message = MessageSend::new_message()
+            .reply("1")
+            .text("abcd")
+            .image_url("a.png")
+            .text("efgh")
+            .build();
+        let chunks = split(message, 6);
+        assert_eq!(texts(&chunks), ["abcdef", "gh"]);
+        let MessageSend::Array(first) = &chunks[0] else {
+            unreachable!()
+        };
+        assert_eq!(first.len(), 4);
+    }
+
+    #[test]
+    fn test_limiter() {
+        let config = OutboundConfig {
+            global_rate: 2.0,
+            global_burst: 3.0,
+            target_rate: 1.0,
+            target_burst: 2.0,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let mut limiter = Limiter::new(now);
+        let (a, b) = (Target::Group(1), Target::Group(2));
+
+        assert_eq!(limiter.try_take(a, &config, 0.0, now), None);
+        assert_eq!(limiter.try_take(a, &config, 0.0, now), None);
+        // 单个目标的令牌用完
+        assert_eq!(
+            limiter.try_take(a, &config, 0.0, now),
+            Some(Duration::from_secs(1))
+        );
+        // 需要给排队中的指令回复留出全局令牌
+        assert_eq!(
+            limiter.try_take(b, &config, 1.0, now),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(limiter.try_take(b, &config, 0.0, now), None);
+        // 全局令牌用完
+        assert_eq!(
+            limiter.try_take(b, &config, 0.0, now),
+            Some(Duration::from_millis(500))
+        );
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(limiter.try_take(a, &config, 0.0, later), None);
+    }
+
+    #[tokio::test]
+    async fn test_queue() -> anyhow::Result<()> {
+        let target = Target::Group(9002);
+        // 用完目标的突发令牌，之后每秒才能发送一条
+        for _ in 0..config::current().outbound.target_burst as usize {
+            acquire(target, Priority::Bulk).await;
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let send = |name: &'static str, priority: Priority| {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                acquire(target, priority).await;
+                let _ = tx.send(name);
+            });
+        };
+        let queued = |n: usize| async move {
+            while QUEUES
+                .lock()
+                .unwrap()
+                .get(&target)
+                .map_or(0, |q| q.interactive.len() + q.bulk.len())
+                < n
+            {
+                tokio::task::yield_now().await;
+            }
+        };
+        send("bulk1", Priority::Bulk);
+        queued(1).await;
+        send("bulk2", Priority::Bulk);
+        queued(2).await;
+        send("interactive", Priority::Interactive);
+        queued(3).await;
+
+        let mut order = Vec::new();
+        for _ in 0..3 {
+            order.push(rx.recv().await.unwrap());
+        }
+        // 已在等待的主动消息之前先发指令回复，同一优先级内按到达顺序
+        assert_eq!(order, ["interactive", "bulk1", "bulk2"]);
+        assert!(QUEUES.lock().unwrap().get(&target).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry() -> anyhow::Result<()> {
+        let (bot, _subscribe) = FakeBot::new();
+        let failures = AtomicU32::new(0);
+        bot.script_retcode("send_private_msg", move |_| {
+            if failures.fetch_add(1, Ordering::Relaxed) == 0 {
+                Err((1200, "发送频率过快".to_string()))
+            } else {
+                Ok(serde_json::json!({ "message_id": 1 }))
+            }
+        });
+        let retries = stats().retries;
+
+        // 默认不重试，1200 也可能是消息已发出但响应超时
+        assert!(
+            send_message_to(bot.as_ref(), Target::Private(9001), from_str("提醒"))
+                .await
+                .is_err()
+        );
+        assert_eq!(bot.calls().len(), 1);
+        assert_eq!(stats().retries, retries);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_backoff() -> anyhow::Result<()> {
+        let config = OutboundConfig {
+            rate_limited_retcodes: vec![1200],
+            max_retries: 2,
+            retry_base_ms: 100,
+            ..Default::default()
+        };
+        let failed = serde_json::from_value::<api::ApiResponse<api::SendMsgData>>(
+            serde_json::json!({ "status": "failed", "retcode": 1200, "data": null, "echo": "1" }),
+        )?;
+        assert_eq!(retry_backoff(&config, &failed, 0), Some(100));
+        assert_eq!(retry_backoff(&config, &failed, 1), Some(200));
+        assert_eq!(retry_backoff(&config, &failed, 2), None);
+        assert_eq!(retry_backoff(&OutboundConfig::default(), &failed, 0), None);
+
+        let other = serde_json::from_value::<api::ApiResponse<api::SendMsgData>>(
+            serde_json::json!({ "status": "failed", "retcode": 100, "data": null, "echo": "1" }),
+        )?;
+        assert_eq!(retry_backoff(&config, &other, 0), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use crate::abi::message::{Sender, sender::SenderRole};
+use crate::{api::storage::HotTable, config};
+use anyhow::{Result, bail};
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+
+/// 运行时添加的 Bot 管理员，值为添加者的 QQ 号
+static ADMINS: LazyLock<HotTable<i64, i64>> = LazyLock::new(|| HotTable::new("bot_admin"));
+
+/// 配置中的管理员始终有效，无法通过指令移除
+pub fn is_bot_admin(user_id: i64) -> bool {
+    config::current().bot.admins.contains(&user_id) || ADMINS.get(&user_id).is_some()
+}
+
+/// 配置中与运行时添加的全部 Bot 管理员
+pub fn bot_admins() -> Vec<i64> {
+    let mut ids = config::current().bot.admins.clone();
+    ids.extend(ADMINS.keys());
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+pub fn add_bot_admin(user_id: i64, operator: i64) -> Result<()> {
+    ADMINS.insert(user_id, Arc::new(operator))
+}
+
+pub fn remove_bot_admin(user_id: i64) -> Result<()> {
+    if config::current().bot.admins.contains(&user_id) {
+        bail!("{} 是配置文件中的管理员，无法移除", user_id);
+    }
+    ADMINS.remove(&user_id)
+}
+
+/// 指令的权限要求，Bot 管理员可以执行所有指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Anyone,
+    /// 群管理员或群主
+    GroupAdmin,
+    /// 群主
+    Owner,
+    BotAdmin,
+}
+
+impl Permission {
+    pub fn allows(self, sender: &Sender) -> bool {
+        let is_admin = || sender.user_id.is_some_and(is_bot_admin);
+        match self {
+            Permission::Anyone => true,
+            Permission::GroupAdmin => {
+                matches!(
+                    sender.role,
+                    Some(SenderRole::GroupAdmin | SenderRole::GroupOwner)
+                ) || is_admin()
+            }
+            Permission::Owner => matches!(sender.role, Some(SenderRole::GroupOwner)) || is_admin(),
+            Permission::BotAdmin => is_admin(),
+        }
+    }
+
+    /// 权限不足时回复的提示
+    pub fn refusal(self) -> String {
+        format!("权限不足: 该指令仅限{}使用", self)
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Permission::Anyone => "所有人",
+            Permission::GroupAdmin => "群管理员",
+            Permission::Owner => "群主",
+            Permission::BotAdmin => "Bot 管理员",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::FakeBot;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn sender(user_id: i64, role: Option<SenderRole>) -> Sender {
+        Sender {
+            nickname: None,
+            user_id: Some(user_id),
+            card: None,
+            role,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allows() {
+        let member = sender(456, Some(SenderRole::GroupMember));
+        let admin = sender(456, Some(SenderRole::GroupAdmin));
+        let owner = sender(456, Some(SenderRole::GroupOwner));
+
+        assert!(Permission::Anyone.allows(&member));
+        assert!(!Permission::GroupAdmin.allows(&member));
+        assert!(Permission::GroupAdmin.allows(&admin));
+        assert!(Permission::GroupAdmin.allows(&owner));
+        assert!(!Permission::Owner.allows(&admin));
+        assert!(Permission::Owner.allows(&owner));
+        assert!(!Permission::BotAdmin.allows(&owner));
+    }
+
+    #[tokio::test]
+    async fn test_refusal_and_help() -> anyhow::Result<()> {
+        let bot = FakeBot::spawn_router();
+        bot.group_message(123, 456, "/admin list")?;
+
+        let call = bot
+            .wait_for("send_group_msg", TIMEOUT)
+            .await
+            .expect("没有收到拒绝消息");
+        assert!(
+            call.params
+                .to_string()
+                .contains(&Permission::BotAdmin.refusal())
+        );
+
+        bot.private_message(456, "/help")?;
+        let call = bot
+            .wait_for("send_private_forward_msg", TIMEOUT)
+            .await
+            .expect("没有收到帮助信息");
+        let help = call.params.to_string();
+        assert!(help.contains("指令: help"));
+        assert!(!help.contains("指令: admin"));
+
+        Ok(())
+    }
+}
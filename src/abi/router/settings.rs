@@ -0,0 +1,325 @@
+use crate::abi::message::Target;
+use crate::api::storage::ColdTable;
+use anyhow::{Result, anyhow, bail};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+/// 按北京时间 (UTC+8) 计算免打扰时段
+const HOUR_OFFSET_SECS: u64 = 8 * 3600;
+
+/// 无论群设置如何都可以使用的指令，保证群管理员总能改回设置
+const ALWAYS_ALLOWED: &[&str] = &["config", "help"];
+
+static SETTINGS_DB: LazyLock<ColdTable<i64, GroupSettings>> =
+    LazyLock::new(|| ColdTable::new("group_settings"));
+
+/// 路由中需要同步读取设置，因此启动时全部载入内存，修改时同时写回数据库
+static CACHE: LazyLock<DashMap<i64, Arc<GroupSettings>>> = LazyLock::new(DashMap::new);
+
+/// 回复的发送方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplyStyle {
+    /// 合并转发
+    #[default]
+    Forward,
+    /// 逐条发送普通消息
+    Plain,
+    /// 合并为一条引用原消息并 @ 发送者的消息
+    Quote,
+}
+
+/// 免打扰时段，`start` 到 `end` 点（北京时间，可跨零点）内不响应指令与 LLM 回复
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl QuietHours {
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// 单个群的功能开关与设置，私聊始终使用默认值
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GroupSettings {
+    /// 是否进行 LLM 回复
+    pub llm: bool,
+    /// 是否归档聊天记录
+    pub archive: bool,
+    /// 允许使用的指令，为 None 时允许全部指令
+    pub commands: Option<Vec<String>>,
+    pub reply_style: ReplyStyle,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        GroupSettings {
+            llm: true,
+            archive: true,
+            commands: None,
+            reply_style: ReplyStyle::Forward,
+            quiet_hours: None,
+        }
+    }
+}
+
+fn on_off(value: &str) -> Result<bool> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => bail!("取值只能是 on 或 off"),
+    }
+}
+
+fn parse_hour(value: &str) -> Result<u8> {
+    match value.parse::<u8>() {
+        Ok(hour) if hour < 24 => Ok(hour),
+        _ => bail!("{} 不是有效的小时数 (0-23)", value),
+    }
+}
+
+impl GroupSettings {
+    /// 修改一项设置，`resolve` 用于把指令名或别名转换为指令名
+    pub fn set(
+        &mut self,
+        key: &str,
+        value: &str,
+        resolve: impl Fn(&str) -> Option<&'static str>,
+    ) -> Result<()> {
+        match key {
+            "llm" => self.llm = on_off(value)?,
+            "archive" => self.archive = on_off(value)?,
+            "commands" => {
+                self.commands = if value == "all" {
+                    None
+                } else {
+                    let mut commands = value
+                        .split([',', '，'])
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| {
+                            resolve(name)
+                                .map(str::to_string)
+                                .ok_or(anyhow!("未知的指令: {}", name))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    commands.sort_unstable();
+                    commands.dedup();
+                    Some(commands)
+                };
+            }
+            "style" => {
+                self.reply_style = match value {
+                    "forward" => ReplyStyle::Forward,
+                    "plain" => ReplyStyle::Plain,
+                    "quote" => ReplyStyle::Quote,
+                    _ => bail!("回复方式只能是 forward、plain 或 quote"),
+                }
+            }
+            "quiet" => {
+                self.quiet_hours = if value == "off" {
+                    None
+                } else {
+                    let (start, end) = value
+                        .split_once('-')
+                        .ok_or(anyhow!("免打扰时段的格式为 起始小时-结束小时，如 23-7"))?;
+                    let (start, end) = (parse_hour(start.trim())?, parse_hour(end.trim())?);
+                    if start == end {
+                        bail!("免打扰时段的起止时间不能相同");
+                    }
+                    Some(QuietHours { start, end })
+                };
+            }
+            _ => bail!("未知的设置项: {}", key),
+        }
+        Ok(())
+    }
+
+    pub fn allows_command(&self, name: &str, hour: u8) -> bool {
+        if ALWAYS_ALLOWED.contains(&name) {
+            return true;
+        }
+        if self.is_quiet(hour) {
+            return false;
+        }
+        self.commands
+            .as_ref()
+            .is_none_or(|commands| commands.iter().any(|c| c == name))
+    }
+
+    pub fn is_quiet(&self, hour: u8) -> bool {
+        self.quiet_hours.is_some_and(|quiet| quiet.contains(hour))
+    }
+
+    /// 当前是否应当进行 LLM 回复
+    pub fn llm_enabled(&self) -> bool {
+        self.llm && !self.is_quiet(current_hour())
+    }
+}
+
+impl fmt::Display for GroupSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        writeln!(f, "llm: {}", on_off(self.llm))?;
+        writeln!(f, "archive: {}", on_off(self.archive))?;
+        match &self.commands {
+            Some(commands) => writeln!(f, "commands: {}", commands.join(","))?,
+            None => writeln!(f, "commands: all")?,
+        }
+        let style = match self.reply_style {
+            ReplyStyle::Forward => "forward",
+            ReplyStyle::Plain => "plain",
+            ReplyStyle::Quote => "quote",
+        };
+        writeln!(f, "style: {}", style)?;
+        match self.quiet_hours {
+            Some(quiet) => write!(f, "quiet: {}", quiet),
+            None => write!(f, "quiet: off"),
+        }
+    }
+}
+
+pub fn current_hour() -> u8 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    ((now + HOUR_OFFSET_SECS) % (24 * 3600) / 3600) as u8
+}
+
+/// 启动时从数据库载入所有群的设置
+pub async fn load() {
+    match SETTINGS_DB.get_all().await {
+        Ok(all) => {
+            info!("已载入 {} 个群的设置", all.len());
+            for (group_id, settings) in all {
+                CACHE.insert(group_id, Arc::new(settings));
+            }
+        }
+        // 表尚未创建时同样会失败，此时所有群使用默认设置
+        Err(e) => warn!("载入群设置失败，使用默认设置: {:?}", e),
+    }
+}
+
+/// 目标对应的设置，私聊与未设置过的群返回默认值
+pub fn get(target: Target) -> Arc<GroupSettings> {
+    match target {
+        Target::Group(group_id) => CACHE.get(&group_id).map(|s| s.clone()).unwrap_or_default(),
+        Target::Private(_) => Arc::default(),
+    }
+}
+
+pub async fn save(group_id: i64, settings: GroupSettings) -> Result<()> {
+    SETTINGS_DB.insert(group_id, settings.clone()).await?;
+    CACHE.insert(group_id, Arc::new(settings));
+    Ok(())
+}
+
+/// 指令在目标中是否可用，由 `#[handler]` 生成的代码在权限检查之前调用
+pub fn allows_command(target: Target, name: &str) -> bool {
+    get(target).allows_command(name, current_hour())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::FakeBot;
+    use std::time::Duration;
+
+    fn resolve(name: &str) -> Option<&'static str> {
+        match name {
+            "download" | "下载" => Some("download"),
+            "echo" => Some("echo"),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_set() {
+        let mut settings = GroupSettings::default();
+        settings.set("llm", "off", resolve).unwrap();
+        settings
+            .set("commands", "下载，echo,download", resolve)
+            .unwrap();
+        settings.set("style", "plain", resolve).unwrap();
+        settings.set("quiet", "23-7", resolve).unwrap();
+        assert!(!settings.llm);
+        assert_eq!(
+            settings.commands,
+            Some(vec!["download".to_string(), "echo".to_string()])
+        );
+        assert_eq!(settings.reply_style, ReplyStyle::Plain);
+        assert_eq!(settings.quiet_hours, Some(QuietHours { start: 23, end: 7 }));
+
+        assert!(settings.set("llm", "yes", resolve).is_err());
+        assert!(settings.set("commands", "login", resolve).is_err());
+        assert!(settings.set("quiet", "7-24", resolve).is_err());
+        assert!(settings.set("color", "red", resolve).is_err());
+
+        settings.set("commands", "all", resolve).unwrap();
+        assert_eq!(settings.commands, None);
+        settings.set("style", "quote", resolve).unwrap();
+        assert_eq!(settings.reply_style, ReplyStyle::Quote);
+        assert!(settings.set("style", "card", resolve).is_err());
+    }
+
+    #[test]
+    fn test_allows_command() {
+        let settings = GroupSettings {
+            commands: Some(vec!["echo".to_string()]),
+            quiet_hours: Some(QuietHours { start: 23, end: 7 }),
+            ..Default::default()
+        };
+        assert!(settings.allows_command("echo", 12));
+        assert!(!settings.allows_command("download", 12));
+        assert!(!settings.allows_command("echo", 23));
+        assert!(!settings.allows_command("echo", 3));
+        assert!(settings.allows_command("echo", 7));
+        assert!(settings.allows_command("config", 3));
+    }
+
+    #[tokio::test]
+    async fn test_router_settings() -> anyhow::Result<()> {
+        let disabled = GroupSettings {
+            commands: Some(vec!["help".to_string()]),
+            ..Default::default()
+        };
+        let plain = GroupSettings {
+            reply_style: ReplyStyle::Plain,
+            ..Default::default()
+        };
+        CACHE.insert(1001, Arc::new(disabled));
+        CACHE.insert(1002, Arc::new(plain));
+
+        let bot = FakeBot::spawn_router();
+        bot.group_message(1001, 456, "/echo 你好")?;
+        bot.group_message(1002, 456, "/echo 你好")?;
+
+        let call = bot
+            .wait_for("send_group_msg", Duration::from_secs(5))
+            .await
+            .expect("没有收到普通消息");
+        assert_eq!(call.params["group_id"], 1002);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(bot.calls().len(), 1);
+
+        Ok(())
+    }
+}
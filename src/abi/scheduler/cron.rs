@@ -0,0 +1,187 @@
+use anyhow::{Result, anyhow, bail};
+use std::str::FromStr;
+
+/// 按北京时间 (UTC+8) 计算触发时间
+const OFFSET_SECS: i64 = 8 * 3600;
+const DAY_SECS: i64 = 24 * 3600;
+/// 最多向后查找的天数，足以覆盖只在闰年 2 月 29 日触发的表达式
+const SEARCH_DAYS: i64 = 366 * 8;
+
+/// 五段式 cron 表达式：分 时 日 月 周
+///
+/// 每段支持 `*`、`a`、`a-b`、`*/n`、`a-b/n` 以及用逗号分隔的列表；
+/// 周的取值为 0-7，0 与 7 都表示周日。日与周同时指定时满足其一即可。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or(anyhow!("无效的步长: {}", part))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let parse = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|v| (min..=max).contains(v))
+                .ok_or(anyhow!("{} 超出范围 {}-{}", s, min, max))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (parse(a)?, parse(b)?),
+                // 单个值带步长时表示从该值开始到最大值
+                None if step.is_some() => (parse(range)?, max),
+                None => (parse(range)?, parse(range)?),
+            },
+        };
+        if start > end {
+            bail!("无效的范围: {}", part);
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for Cron {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "cron 表达式需要 5 段 (分 时 日 月 周)，实际为 {} 段",
+                fields.len()
+            );
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Cron {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+/// 自 1970-01-01 起的天数转换为 (年, 月, 日)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl Cron {
+    fn matches_day(&self, days: i64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        if self.months & (1 << month) == 0 {
+            return false;
+        }
+        // 1970-01-01 是周四
+        let weekday = (days + 4).rem_euclid(7);
+        let day_ok = self.days & (1 << day) != 0;
+        let weekday_ok = self.weekdays & (1 << weekday) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday_ok,
+            (false, true) => day_ok,
+            (false, false) => day_ok || weekday_ok,
+        }
+    }
+
+    /// 严格晚于 `after`（Unix 时间戳，秒）的下一次触发时间
+    pub fn next_after(&self, after: i64) -> Option<i64> {
+        let mut local = (after + OFFSET_SECS).div_euclid(60) * 60 + 60;
+        for _ in 0..SEARCH_DAYS {
+            let days = local.div_euclid(DAY_SECS);
+            if self.matches_day(days) {
+                let secs = local.rem_euclid(DAY_SECS);
+                let (start_hour, start_minute) = (secs / 3600, secs % 3600 / 60);
+                for hour in start_hour..24 {
+                    if self.hours & (1 << hour) == 0 {
+                        continue;
+                    }
+                    let first = if hour == start_hour { start_minute } else { 0 };
+                    if let Some(minute) = (first..60).find(|m| self.minutes & (1 << m) != 0) {
+                        return Some(days * DAY_SECS + hour * 3600 + minute * 60 - OFFSET_SECS);
+                    }
+                }
+            }
+            local = (days + 1) * DAY_SECS;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 00:00 (UTC+8)，周一
+    const NEW_YEAR: i64 = 1704038400;
+
+    fn next(expr: &str, after: i64) -> Option<i64> {
+        expr.parse::<Cron>().unwrap().next_after(after)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("0 8 * * *".parse::<Cron>().is_ok());
+        assert!("*/15 9-18/2 1,15 * 1-5".parse::<Cron>().is_ok());
+        assert!("0 8 * *".parse::<Cron>().is_err());
+        assert!("60 8 * * *".parse::<Cron>().is_err());
+        assert!("0 8 0 * *".parse::<Cron>().is_err());
+        assert!("0 18-8 * * *".parse::<Cron>().is_err());
+        assert!("*/0 * * * *".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("0 8 * * *", NEW_YEAR), Some(1704067200));
+        assert_eq!(next("*/15 * * * *", NEW_YEAR), Some(NEW_YEAR + 900));
+        // 严格晚于给定时间
+        assert_eq!(next("0 0 * * *", NEW_YEAR), Some(NEW_YEAR + DAY_SECS));
+        assert_eq!(next("0 9 * * 1", NEW_YEAR), Some(1704070800));
+        assert_eq!(next("0 12 * * 7", NEW_YEAR), Some(1704600000));
+        assert_eq!(next("30 23 31 * *", NEW_YEAR), Some(1706715000));
+        assert_eq!(next("0 0 29 2 *", NEW_YEAR), Some(1709136000));
+        assert_eq!(next("0 0 29 2 *", 1709136000), Some(1835366400));
+        // 日与周同时指定时满足其一即可：3 月 1 日早于之后的第一个周日
+        assert_eq!(next("0 0 1 3 0", 1709136000), Some(1709222400));
+        assert_eq!(next("0 0 31 2 *", NEW_YEAR), None);
+    }
+}
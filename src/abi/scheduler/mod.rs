@@ -0,0 +1,464 @@
+pub mod cron;
+
+use crate::abi::{
+    message::{MessageSend, Target, from_str},
+    network::BotClient,
+    router::context::send_message_to,
+};
+use crate::api::storage::ColdTable;
+use anyhow::{Result, anyhow, bail};
+use cron::Cron;
+use dashmap::{DashMap, DashSet};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Notify, watch};
+use tokio::time;
+use tracing::{debug, error, info, warn};
+
+/// 内置的任务 handler，把 payload 作为文本发送到任务目标
+pub const SEND_MESSAGE: &str = "send_message";
+
+/// 没有任务时也定期醒来，避免系统时间被调整后长时间错过任务
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+/// 执行失败后第一次重试的等待时间，之后每次翻倍
+const RETRY_BASE_SECS: i64 = 10;
+/// 重试等待时间的上限
+const MAX_RETRY_SECS: i64 = 600;
+/// 连续失败超过该次数后放弃本次执行，按正常执行完毕推进
+const MAX_RETRIES: u32 = 8;
+
+static JOB_DB: LazyLock<ColdTable<String, Job>> = LazyLock::new(|| ColdTable::new("scheduled_job"));
+
+static JOBS: LazyLock<DashMap<String, Job>> = LazyLock::new(DashMap::new);
+
+static HANDLERS: LazyLock<DashMap<&'static str, JobHandler>> = LazyLock::new(|| {
+    let handlers = DashMap::new();
+    handlers.insert(SEND_MESSAGE, send_payload as JobHandler);
+    handlers
+});
+
+/// 正在执行的任务，执行结束前不会再次触发
+static RUNNING: LazyLock<DashSet<String>> = LazyLock::new(DashSet::new);
+
+/// 任务连续失败的次数，只保存在内存中
+static FAILURES: LazyLock<DashMap<String, u32>> = LazyLock::new(DashMap::new);
+
+/// 添加或删除任务、任务执行结束后唤醒调度循环重新计算等待时间
+static WAKE: Notify = Notify::const_new();
+
+pub type JobHandler = fn(JobContext) -> BoxFuture<'static, Result<()>>;
+
+/// 任务的触发方式
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Trigger {
+    /// 五段式 cron 表达式，按北京时间计算
+    Cron(String),
+    /// 在指定的 Unix 时间戳（秒）执行一次
+    At(i64),
+}
+
+impl Trigger {
+    /// 严格晚于 `after` 的下一次执行时间，一次性任务已过期时返回 None
+    pub fn next_after(&self, after: i64) -> Result<Option<i64>> {
+        match self {
+            Trigger::Cron(expr) => Ok(expr.parse::<Cron>()?.next_after(after)),
+            Trigger::At(at) => Ok((*at > after).then_some(*at)),
+        }
+    }
+}
+
+/// 停机期间错过执行时间的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedPolicy {
+    /// 直接跳到下一次执行时间
+    #[default]
+    Skip,
+    /// 启动后立即补跑一次，多次错过也只补跑一次
+    CatchUp,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: String,
+    /// 执行任务的 handler 名，需先通过 `register_handler` 注册
+    pub handler: String,
+    pub trigger: Trigger,
+    pub missed: MissedPolicy,
+    pub target: Target,
+    /// 交给 handler 的任意数据，如提醒的内容
+    pub payload: String,
+    pub next_run: Option<i64>,
+}
+
+impl Job {
+    pub fn new(
+        id: impl Into<String>,
+        handler: impl Into<String>,
+        trigger: Trigger,
+        target: Target,
+        payload: impl Into<String>,
+    ) -> Result<Self> {
+        let next_run = trigger.next_after(now())?;
+        if next_run.is_none() {
+            bail!("任务的执行时间已经过去");
+        }
+        Ok(Job {
+            id: id.into(),
+            handler: handler.into(),
+            trigger,
+            missed: MissedPolicy::default(),
+            target,
+            payload: payload.into(),
+            next_run,
+        })
+    }
+
+    pub fn missed(mut self, policy: MissedPolicy) -> Self {
+        self.missed = policy;
+        self
+    }
+
+    /// 启动时处理停机期间错过的执行
+    ///
+    /// 需要补跑的任务保留已过去的执行时间，连接 Napcat 后由调度循环执行一次。
+    fn recover(&mut self, now: i64) -> Result<()> {
+        if self.missed == MissedPolicy::Skip && self.next_run.is_some_and(|t| t <= now) {
+            self.next_run = self.trigger.next_after(now)?;
+        }
+        Ok(())
+    }
+}
+
+/// 类型擦除后的 `BotClient`，使任务无需 Context 即可主动发送消息
+pub trait MessageSender: Send + Sync {
+    fn send(&self, target: Target, message: MessageSend) -> BoxFuture<'_, Result<()>>;
+    fn connection(&self) -> watch::Receiver<bool>;
+}
+
+impl<T: BotClient + Send + Sync> MessageSender for T {
+    fn send(&self, target: Target, message: MessageSend) -> BoxFuture<'_, Result<()>> {
+        Box::pin(send_message_to(self, target, message))
+    }
+
+    fn connection(&self) -> watch::Receiver<bool> {
+        BotClient::connection(self)
+    }
+}
+
+/// 任务执行时的上下文
+pub struct JobContext {
+    pub job: Job,
+    sender: Arc<dyn MessageSender>,
+}
+
+impl JobContext {
+    /// 向任务的目标发送消息
+    pub async fn send_message(&self, message: MessageSend) -> Result<()> {
+        self.sender.send(self.job.target, message).await
+    }
+
+    pub async fn send_message_to(&self, target: Target, message: MessageSend) -> Result<()> {
+        self.sender.send(target, message).await
+    }
+}
+
+fn send_payload(ctx: JobContext) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move { ctx.send_message(from_str(ctx.job.payload.clone())).await })
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// 注册任务 handler，同名时覆盖
+pub fn register_handler(name: &'static str, handler: JobHandler) {
+    HANDLERS.insert(name, handler);
+}
+
+/// 添加或替换任务并写入数据库
+pub async fn add(job: Job) -> Result<()> {
+    if !HANDLERS.contains_key(job.handler.as_str()) {
+        bail!("未注册的任务 handler: {}", job.handler);
+    }
+    JOB_DB.insert(job.id.clone(), job.clone()).await?;
+    debug!("添加定时任务: {:?}", job);
+    JOBS.insert(job.id.clone(), job);
+    WAKE.notify_one();
+    Ok(())
+}
+
+/// 删除任务，任务不存在时返回 false
+pub async fn remove(id: &str) -> Result<bool> {
+    if JOBS.remove(id).is_none() {
+        return Ok(false);
+    }
+    JOB_DB.remove(id.to_string()).await?;
+    WAKE.notify_one();
+    Ok(true)
+}
+
+/// 当前所有任务，按下一次执行时间排序
+pub fn jobs() -> Vec<Job> {
+    let mut jobs = JOBS.iter().map(|job| job.clone()).collect::<Vec<_>>();
+    jobs.sort_by_key(|job| job.next_run);
+    jobs
+}
+
+/// 从数据库载入任务并启动调度循环
+pub async fn start<T: BotClient + Send + Sync + 'static>(client: Arc<T>) {
+    let sender: Arc<dyn MessageSender> = client;
+    let now = now();
+
+    let saved = match JOB_DB.get_all().await {
+        Ok(saved) => saved,
+        // 表尚未创建时同样会失败
+        Err(e) => {
+            warn!("载入定时任务失败: {:?}", e);
+            Vec::new()
+        }
+    };
+    info!("已载入 {} 个定时任务", saved.len());
+    for (_, mut job) in saved {
+        if let Err(e) = job.recover(now) {
+            // 保留在数据库中，便于排查后手动删除
+            error!("定时任务 {} 的触发条件无法解析，暂停执行: {:?}", job.id, e);
+            job.next_run = None;
+            JOBS.insert(job.id.clone(), job);
+            continue;
+        }
+        if job.next_run.is_some_and(|t| t <= now) {
+            info!("定时任务 {} 将在连接 Napcat 后补跑", job.id);
+        }
+        save(job).await;
+    }
+
+    let shutdown = crate::shutdown::global();
+    let mut connected = sender.connection();
+    tokio::spawn(async move {
+        loop {
+            // 断开期间发送必然失败，重新连接后再执行到期的任务
+            if !*connected.borrow_and_update() {
+                tokio::select! {
+                    biased;
+                    _ = shutdown.triggered() => break,
+                    _ = connected.wait_for(|connected| *connected) => {}
+                }
+            }
+
+            let now = self::now();
+            let due = JOBS
+                .iter()
+                .filter(|job| job.next_run.is_some_and(|t| t <= now))
+                .filter(|job| !RUNNING.contains(&job.id))
+                .map(|job| job.clone())
+                .collect::<Vec<_>>();
+            for job in due {
+                run(&sender, job);
+            }
+
+            let sleep = JOBS
+                .iter()
+                .filter(|job| !RUNNING.contains(&job.id))
+                .filter_map(|job| job.next_run)
+                .min()
+                .map(|t| Duration::from_secs((t - now).max(0) as u64))
+                .map_or(MAX_SLEEP, |d| d.min(MAX_SLEEP));
+            tokio::select! {
+                biased;
+                _ = shutdown.triggered() => break,
+                _ = time::sleep(sleep) => {}
+                _ = WAKE.notified() => {}
+            }
+        }
+        debug!("定时任务调度已停止");
+    });
+}
+
+/// 更新任务的下一次执行时间，不再执行的任务从内存与数据库中删除
+async fn save(job: Job) {
+    let result = if job.next_run.is_some() {
+        JOBS.insert(job.id.clone(), job.clone());
+        JOB_DB.insert(job.id.clone(), job).await
+    } else {
+        debug!("定时任务 {} 已不再执行，删除", job.id);
+        JOBS.remove(&job.id);
+        JOB_DB.remove(job.id).await
+    };
+    if let Err(e) = result {
+        error!("保存定时任务失败: {:?}", e);
+    }
+}
+
+/// 启动任务，执行结束后再根据结果推进或重试
+fn run(sender: &Arc<dyn MessageSender>, job: Job) {
+    let handler = HANDLERS.get(job.handler.as_str()).map(|h| *h);
+    let ctx = JobContext {
+        job: job.clone(),
+        sender: sender.clone(),
+    };
+    RUNNING.insert(job.id.clone());
+    // 停机时等待执行中的任务结束
+    crate::shutdown::global().spawn(async move {
+        debug!("执行定时任务 {}", job.id);
+        let result = match handler {
+            Some(handler) => handler(ctx).await,
+            None => Err(anyhow!("handler {} 未注册", job.handler)),
+        };
+        let id = job.id.clone();
+        finish(job, result, now()).await;
+        RUNNING.remove(&id);
+        WAKE.notify_one();
+    });
+}
+
+/// 执行成功后推进到下一次执行时间，失败时按退避时间重试
+///
+/// 重试时间只写入内存，数据库中保留上一次成功后的执行时间，重试期间停机的任务重启后按错过处理。
+async fn finish(mut job: Job, result: Result<()>, now: i64) {
+    // 执行期间任务被删除或替换时以新的任务为准
+    if JOBS.get(&job.id).is_none_or(|current| *current != job) {
+        FAILURES.remove(&job.id);
+        return;
+    }
+
+    if let Err(e) = result {
+        let failures = *FAILURES
+            .entry(job.id.clone())
+            .and_modify(|n| *n += 1)
+            .or_insert(1);
+        if failures <= MAX_RETRIES {
+            let delay = (RETRY_BASE_SECS << (failures - 1)).min(MAX_RETRY_SECS);
+            warn!(
+                "定时任务 {} 第 {} 次执行失败，{} 秒后重试: {:?}",
+                job.id, failures, delay, e
+            );
+            job.next_run = Some(now + delay);
+            JOBS.insert(job.id.clone(), job);
+            return;
+        }
+        error!(
+            "定时任务 {} 连续 {} 次执行失败，放弃本次执行: {:?}",
+            job.id, failures, e
+        );
+    }
+
+    FAILURES.remove(&job.id);
+    match job.trigger.next_after(now) {
+        Ok(next_run) => {
+            job.next_run = next_run;
+            save(job).await;
+        }
+        Err(e) => {
+            error!("定时任务 {} 的触发条件无法解析，暂停执行: {:?}", job.id, e);
+            job.next_run = None;
+            JOBS.insert(job.id.clone(), job);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::FakeBot;
+    use anyhow::anyhow;
+
+    fn job_at(id: &str, next_run: i64, trigger: Trigger, missed: MissedPolicy) -> Job {
+        Job {
+            id: id.to_string(),
+            handler: SEND_MESSAGE.to_string(),
+            trigger,
+            missed,
+            target: Target::Group(123),
+            payload: String::new(),
+            next_run: Some(next_run),
+        }
+    }
+
+    #[test]
+    fn test_recover() -> anyhow::Result<()> {
+        let now = 1704038400;
+        let cron = Trigger::Cron("0 8 * * *".to_string());
+
+        let mut job = job_at("skip", now - 3600, cron.clone(), MissedPolicy::Skip);
+        job.recover(now)?;
+        assert_eq!(job.next_run, Some(now + 8 * 3600));
+
+        // 补跑的任务保持到期，由调度循环在连接后执行
+        let mut job = job_at("catch_up", now - 3600, cron.clone(), MissedPolicy::CatchUp);
+        job.recover(now)?;
+        assert_eq!(job.next_run, Some(now - 3600));
+
+        let mut job = job_at("once", now - 60, Trigger::At(now - 60), MissedPolicy::Skip);
+        job.recover(now)?;
+        assert_eq!(job.next_run, None);
+
+        let mut job = job_at("future", now + 60, cron, MissedPolicy::Skip);
+        job.recover(now)?;
+        assert_eq!(job.next_run, Some(now + 60));
+
+        let broken = Trigger::Cron("0 8 * *".to_string());
+        let mut job = job_at("broken", now - 60, broken, MissedPolicy::Skip);
+        assert!(job.recover(now).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry() {
+        // 执行时间放在将来，避免被其他测试启动的调度循环执行
+        let at = now() + 3600;
+        let job = job_at("test_retry", at, Trigger::At(at), MissedPolicy::Skip);
+        JOBS.insert(job.id.clone(), job.clone());
+
+        // 失败时一次性任务不会被删除，而是退避后重试
+        finish(job, Err(anyhow!("连接已断开")), at).await;
+        let job = JOBS.get("test_retry").unwrap().clone();
+        assert_eq!(job.next_run, Some(at + RETRY_BASE_SECS));
+        finish(job, Err(anyhow!("连接已断开")), at).await;
+        let job = JOBS.get("test_retry").unwrap().clone();
+        assert_eq!(job.next_run, Some(at + 2 * RETRY_BASE_SECS));
+
+        finish(job, Ok(()), at + 2 * RETRY_BASE_SECS).await;
+        assert!(JOBS.get("test_retry").is_none());
+        assert!(FAILURES.get("test_retry").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler() -> anyhow::Result<()> {
+        let (bot, _subscribe) = FakeBot::new();
+        start(bot.clone()).await;
+
+        assert!(Job::new("past", SEND_MESSAGE, Trigger::At(0), Target::Group(1), "").is_err());
+        let job = Job::new(
+            "test_scheduler",
+            "unknown",
+            Trigger::At(now() + 1),
+            Target::Group(123),
+            "提醒",
+        )?;
+        assert!(add(job.clone()).await.is_err());
+
+        add(Job {
+            handler: SEND_MESSAGE.to_string(),
+            ..job
+        })
+        .await?;
+        let call = bot
+            .wait_for("send_group_msg", Duration::from_secs(5))
+            .await
+            .ok_or(anyhow!("定时任务没有执行"))?;
+        assert_eq!(call.params["group_id"], 123);
+        assert!(call.params.to_string().contains("提醒"));
+
+        // 一次性任务执行后被删除
+        time::sleep(Duration::from_millis(100)).await;
+        assert!(JOBS.get("test_scheduler").is_none());
+
+        Ok(())
+    }
+}
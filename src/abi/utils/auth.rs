@@ -0,0 +1,52 @@
+use tokio_tungstenite::tungstenite::http::{Request, header};
+
+/// 校验 OneBot 风格的 Bearer 头或 access_token 查询参数，未配置 token 时一律放行
+pub fn check_token<B>(request: &Request<B>, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    let from_header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.strip_prefix("Bearer ")
+                .or_else(|| v.strip_prefix("Token "))
+        });
+    if from_header.is_some_and(|t| t.trim() == token) {
+        return true;
+    }
+
+    request.uri().query().is_some_and(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .any(|(k, v)| k == "access_token" && v == token)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str, authorization: Option<&str>) -> Request<()> {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_check_token() {
+        let token = Some("secret");
+        assert!(check_token(&request("/", None), None));
+        assert!(check_token(&request("/", Some("Bearer secret")), token));
+        assert!(check_token(&request("/", Some("Token secret")), token));
+        assert!(check_token(&request("/?access_token=secret", None), token));
+
+        assert!(!check_token(&request("/", None), token));
+        assert!(!check_token(&request("/", Some("Bearer wrong")), token));
+        assert!(!check_token(&request("/", Some("secret")), token));
+        assert!(!check_token(&request("/?access_token=wrong", None), token));
+    }
+}
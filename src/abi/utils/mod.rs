@@ -1,3 +1,5 @@
+mod auth;
 mod json;
 
+pub use auth::*;
 pub use json::*;
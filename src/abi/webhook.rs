@@ -0,0 +1,205 @@
+use crate::abi::utils::check_token;
+use crate::abi::websocket::{BotHandler, BotTransport};
+use crate::config::ServerConfig;
+use anyhow::Result;
+use axum::{
+    Router,
+    body::{self, Body},
+    extract::{Request, State},
+    http::StatusCode,
+};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Utf8Bytes;
+use tracing::{debug, error, info, warn};
+
+/// 单个上报事件的大小上限
+const MAX_EVENT_BYTES: usize = 16 * 1024 * 1024;
+
+struct WebhookState<T: BotHandler> {
+    handler: Arc<T>,
+    access_token: Option<String>,
+}
+
+async fn receive_event<T: BotHandler>(
+    State(state): State<Arc<WebhookState<T>>>,
+    request: Request<Body>,
+) -> StatusCode {
+    if !check_token(&request, state.access_token.as_deref()) {
+        warn!("拒绝了 access_token 不正确的事件上报");
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let bytes = match body::to_bytes(request.into_body(), MAX_EVENT_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("读取事件上报失败: {:?}", e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    match Utf8Bytes::try_from(bytes) {
+        Ok(event) => {
+            state.handler.handle_event(event).await;
+            // 不使用快速操作，空响应即可
+            StatusCode::NO_CONTENT
+        }
+        Err(e) => {
+            error!("事件上报不是合法的 UTF-8: {:?}", e);
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+/// HTTP 上报服务端，接收 Napcat POST 到 webhook_addr 任意路径上的事件
+pub struct BotWebhookServer<T: BotHandler> {
+    config: ServerConfig,
+    handler: Arc<T>,
+
+    serve_task: Option<JoinHandle<()>>,
+}
+
+impl<T: BotHandler> BotWebhookServer<T> {
+    pub fn new(config: ServerConfig, handler: T) -> Self {
+        BotWebhookServer {
+            config,
+            handler: Arc::new(handler),
+            serve_task: None,
+        }
+    }
+
+    pub async fn listen(&mut self) -> Result<()> {
+        let state = Arc::new(WebhookState {
+            handler: self.handler.clone(),
+            access_token: self.config.access_token.clone(),
+        });
+        let app = Router::new()
+            .fallback(axum::routing::post(receive_event::<T>))
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind(&self.config.webhook_addr).await?;
+        info!("HTTP 上报服务已启动: {}", self.config.webhook_addr);
+        debug!(?self.config);
+
+        self.serve_task = Some(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!("HTTP 上报服务异常退出: {:?}", e);
+            }
+        }));
+        self.handler.on_connect().await;
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self) {
+        let Some(task) = self.serve_task.take() else {
+            return;
+        };
+        task.abort();
+
+        let handler = self.handler.clone();
+        tokio::spawn(async move {
+            handler.on_disconnect().await;
+        });
+        info!("HTTP 上报服务已关闭");
+    }
+}
+
+impl<T: BotHandler> BotTransport<T> for BotWebhookServer<T> {
+    fn handler(&self) -> Arc<T> {
+        self.handler.clone()
+    }
+
+    fn disconnect(&mut self) {
+        BotWebhookServer::disconnect(self);
+    }
+}
+
+impl<T: BotHandler> Drop for BotWebhookServer<T> {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use tokio::sync::mpsc;
+
+    /// 把收到的事件原样转发出来
+    struct Capture(mpsc::UnboundedSender<String>);
+
+    #[async_trait]
+    impl BotHandler for Capture {
+        async fn init(
+            &self,
+            _event: mpsc::UnboundedSender<String>,
+            _api: mpsc::UnboundedSender<String>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn handle_api(&self, _message: Utf8Bytes) {}
+
+        async fn handle_event(&self, event: Utf8Bytes) {
+            let _ = self.0.send(event.to_string());
+        }
+
+        async fn on_connect(&self) {}
+
+        async fn on_disconnect(&self) {}
+    }
+
+    async fn start(
+        access_token: Option<&str>,
+    ) -> Result<(
+        BotWebhookServer<Capture>,
+        String,
+        mpsc::UnboundedReceiver<String>,
+    )> {
+        // 先占用一个空闲端口再交给服务端监听
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await?
+            .local_addr()?
+            .to_string();
+        let config = ServerConfig {
+            webhook_addr: addr.clone(),
+            access_token: access_token.map(str::to_string),
+            ..Default::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut server = BotWebhookServer::new(config, Capture(tx));
+        server.listen().await?;
+        Ok((server, format!("http://{}/", addr), rx))
+    }
+
+    #[tokio::test]
+    async fn test_receive_event() -> Result<()> {
+        let (_server, url, mut rx) = start(Some("secret")).await?;
+        let client = reqwest::Client::new();
+        let event = r#"{"post_type":"meta_event"}"#;
+
+        let response = client
+            .post(&url)
+            .bearer_auth("secret")
+            .body(event)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(rx.recv().await.as_deref(), Some(event));
+
+        let response = client.post(&url).body(event).send().await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client
+            .post(&url)
+            .bearer_auth("wrong")
+            .body(event)
+            .send()
+            .await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(rx.try_recv().is_err());
+
+        Ok(())
+    }
+}
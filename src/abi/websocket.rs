@@ -1,15 +1,27 @@
+use crate::abi::echo::echo_fail_all;
 use crate::config::ServerConfig;
 use anyhow::Result;
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use futures_util::sink::SinkExt;
+use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
 use tokio_tungstenite::{
-    connect_async,
-    tungstenite::{Message, Utf8Bytes},
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{
+        self, Message, Utf8Bytes,
+        client::IntoClientRequest,
+        handshake::client::Request,
+        http::{HeaderValue, StatusCode, header},
+    },
 };
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
 
 #[async_trait]
 pub trait BotHandler: Send + Sync + 'static {
@@ -24,15 +36,115 @@ pub trait BotHandler: Send + Sync + 'static {
     async fn on_disconnect(&self);
 }
 
+/// 持有与 Napcat 之间连接的传输层，路由器只通过它取得 handler 并在退出时断开
+#[async_trait]
+pub trait BotTransport<T: BotHandler>: Send {
+    fn handler(&self) -> Arc<T>;
+    fn disconnect(&mut self);
+
+    /// 停机时调用，默认直接断开；需要与对方完成关闭握手的传输层可以覆盖
+    async fn close(&mut self) {
+        self.disconnect();
+    }
+}
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 重连间隔的上限，避免退避时间无限增长
+const MAX_RECONNECT_INTERVAL: Duration = Duration::from_secs(300);
+/// 停机时等待关闭握手完成的最长时间
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 握手被服务器以 401/403 拒绝，与网络错误区分开，便于启动时定位 token 配置问题
+#[derive(Debug)]
+pub struct AuthError {
+    pub endpoint: &'static str,
+    pub status: StatusCode,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "连接 {} 鉴权失败 ({})，请检查 access_token 配置",
+            self.endpoint, self.status
+        )
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// 构造带鉴权信息的握手请求，同时携带 Bearer 头与 access_token 查询参数以兼容只识别其一的实现
+fn build_request(config: &ServerConfig, endpoint: &'static str) -> Result<Request> {
+    let mut url = format!("ws://{}:{}{}", config.host, config.port, endpoint);
+    if let Some(token) = &config.access_token {
+        url.push_str("?access_token=");
+        url.extend(url::form_urlencoded::byte_serialize(token.as_bytes()));
+    }
+
+    let mut request = url.into_client_request()?;
+    if let Some(token) = &config.access_token {
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token))?,
+        );
+    }
+
+    Ok(request)
+}
+
+/// 建立单个 WebSocket 连接，鉴权失败时返回 `AuthError`
+async fn connect_endpoint(config: &ServerConfig, endpoint: &'static str) -> Result<WsStream> {
+    match connect_async(build_request(config, endpoint)?).await {
+        Ok((ws_stream, _)) => Ok(ws_stream),
+        Err(tungstenite::Error::Http(response))
+            if matches!(
+                response.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            ) =>
+        {
+            Err(AuthError {
+                endpoint,
+                status: response.status(),
+            }
+            .into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 一次成功建立的连接，持有 `/event` 与 `/api` 的读写任务
+struct Connection {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Connection {
+    /// 任意一个读写任务结束即视为连接已断开
+    async fn closed(&mut self) {
+        let _ = futures_util::future::select_all(self.tasks.iter_mut()).await;
+    }
+
+    /// 等待所有读写任务结束，用于停机时等待关闭握手完成
+    async fn finished(&mut self) {
+        futures_util::future::join_all(self.tasks.iter_mut()).await;
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
 pub struct BotWebsocketClient<T: BotHandler> {
     config: ServerConfig,
     pub handler: Arc<T>,
 
-    event_read_task: Option<tokio::task::JoinHandle<()>>,
-    event_write_task: Option<tokio::task::JoinHandle<()>>,
-
-    api_read_task: Option<tokio::task::JoinHandle<()>>,
-    api_write_task: Option<tokio::task::JoinHandle<()>>,
+    supervisor_task: Option<JoinHandle<()>>,
+    /// 取消后写任务发出 Close 帧，监视任务不再重连
+    closing: CancellationToken,
 }
 
 impl<T: BotHandler> BotWebsocketClient<T> {
@@ -40,30 +152,50 @@ impl<T: BotHandler> BotWebsocketClient<T> {
         BotWebsocketClient {
             config,
             handler: Arc::new(handler),
-            event_read_task: None,
-            event_write_task: None,
-            api_read_task: None,
-            api_write_task: None,
+            supervisor_task: None,
+            closing: CancellationToken::new(),
         }
     }
 
+    /// 建立首次连接，之后由后台任务负责断线重连
     pub async fn connect(&mut self) -> Result<()> {
+        let connection = Self::establish(&self.config, &self.handler, &self.closing).await?;
+
+        self.supervisor_task = Some(tokio::spawn(Self::supervise(
+            self.config.clone(),
+            self.handler.clone(),
+            connection,
+            self.closing.clone(),
+        )));
+
+        Ok(())
+    }
+
+    async fn establish(
+        config: &ServerConfig,
+        handler: &Arc<T>,
+        closing: &CancellationToken,
+    ) -> Result<Connection> {
         info!(
             "正在连接到 WebSocket 服务器... {}:{}",
-            self.config.host, self.config.port
+            config.host, config.port
         );
-        debug!(?self.config);
+        debug!(?config);
+
+        // 中途失败时 Connection 被丢弃，已启动的任务会一并终止
+        let mut connection = Connection {
+            tasks: Vec::with_capacity(4),
+        };
 
-        let url_event = format!("ws://{}:{}/event", self.config.host, self.config.port);
-        let (ws_stream, _) = connect_async(&url_event).await?;
+        let ws_stream = connect_endpoint(config, "/event").await?;
         let (mut write_event, mut read_event) = ws_stream.split();
 
-        let handler = self.handler.clone();
+        let h = handler.clone();
 
-        self.event_read_task = Some(tokio::spawn(async move {
+        connection.tasks.push(tokio::spawn(async move {
             while let Some(message) = read_event.next().await {
                 if let Ok(Message::Text(msg)) = message {
-                    let h = handler.clone();
+                    let h = h.clone();
                     tokio::spawn(async move {
                         h.handle_event(msg).await;
                     });
@@ -73,25 +205,29 @@ impl<T: BotHandler> BotWebsocketClient<T> {
 
         let (event_sender, mut event_receiver) = mpsc::unbounded_channel::<String>();
 
-        self.event_write_task = Some(tokio::spawn(async move {
-            while let Some(msg) = event_receiver.recv().await {
+        let closing_event = closing.clone();
+        connection.tasks.push(tokio::spawn(async move {
+            while let Some(msg) = tokio::select! {
+                _ = closing_event.cancelled() => None,
+                msg = event_receiver.recv() => msg,
+            } {
                 if let Err(e) = write_event.send(Message::Text(msg.into())).await {
                     error!("传输Event失败通过 WsWriter: {:?}", e);
                     break;
                 }
             }
+            let _ = write_event.close().await;
         }));
 
-        let url_api = format!("ws://{}:{}/api", self.config.host, self.config.port);
-        let (ws_stream, _) = connect_async(&url_api).await?;
+        let ws_stream = connect_endpoint(config, "/api").await?;
         let (mut write_api, mut read_api) = ws_stream.split();
 
-        let handler = self.handler.clone();
+        let h = handler.clone();
 
-        self.api_read_task = Some(tokio::spawn(async move {
+        connection.tasks.push(tokio::spawn(async move {
             while let Some(message) = read_api.next().await {
                 if let Ok(Message::Text(msg)) = message {
-                    let h = handler.clone();
+                    let h = h.clone();
                     tokio::spawn(async move {
                         h.handle_api(msg).await;
                     });
@@ -101,34 +237,79 @@ impl<T: BotHandler> BotWebsocketClient<T> {
 
         let (api_sender, mut api_receiver) = mpsc::unbounded_channel::<String>();
 
-        self.api_write_task = Some(tokio::spawn(async move {
-            while let Some(msg) = api_receiver.recv().await {
+        let closing_api = closing.clone();
+        connection.tasks.push(tokio::spawn(async move {
+            while let Some(msg) = tokio::select! {
+                _ = closing_api.cancelled() => None,
+                msg = api_receiver.recv() => msg,
+            } {
                 if let Err(e) = write_api.send(Message::Text(msg.into())).await {
                     error!("传输Message失败通过 WsWriter: {:?}", e);
                     break;
                 }
             }
+            let _ = write_api.close().await;
         }));
 
-        self.handler.init(event_sender, api_sender).await?;
-        self.handler.on_connect().await;
+        handler.init(event_sender, api_sender).await?;
+        handler.on_connect().await;
 
-        Ok(())
+        Ok(connection)
     }
 
-    pub fn disconnect(&mut self) {
-        if let Some(task) = self.event_read_task.take() {
-            task.abort();
-        }
-        if let Some(task) = self.event_write_task.take() {
-            task.abort();
-        }
-        if let Some(task) = self.api_read_task.take() {
-            task.abort();
-        }
-        if let Some(task) = self.api_write_task.take() {
-            task.abort();
+    /// 监视当前连接，断开后按指数退避重连
+    async fn supervise(
+        config: ServerConfig,
+        handler: Arc<T>,
+        mut connection: Connection,
+        closing: CancellationToken,
+    ) {
+        let base_interval = Duration::from_secs(config.reconnect_interval_secs.max(1));
+
+        loop {
+            tokio::select! {
+                _ = connection.closed() => {}
+                _ = closing.cancelled() => {
+                    // 写任务发出 Close 帧后，等待对方确认使读任务自然结束
+                    let _ = time::timeout(CLOSE_TIMEOUT, connection.finished()).await;
+                    return;
+                }
+            }
+            drop(connection);
+
+            warn!("与 WebSocket 服务器的连接已断开，准备重连");
+            echo_fail_all();
+            handler.on_disconnect().await;
+
+            let mut interval = base_interval;
+            connection = loop {
+                info!("将在 {} 秒后尝试重连", interval.as_secs());
+                tokio::select! {
+                    _ = time::sleep(interval) => {}
+                    _ = closing.cancelled() => return,
+                }
+
+                match Self::establish(&config, &handler, &closing).await {
+                    Ok(connection) => break connection,
+                    Err(e) => {
+                        error!("重连 WebSocket 服务器失败: {:?}", e);
+                        interval = (interval * 2).min(MAX_RECONNECT_INTERVAL);
+                    }
+                }
+            };
+
+            info!("已重新连接到 WebSocket 服务器");
         }
+    }
+
+    pub fn disconnect(&mut self) {
+        // 终止监视任务会丢弃其持有的 Connection，从而终止所有读写任务
+        let Some(task) = self.supervisor_task.take() else {
+            return;
+        };
+        task.abort();
+        echo_fail_all();
+
         // Cannot await in drop; try to call on_disconnect if runtime allows elsewhere.
         let handler = self.handler.clone();
         tokio::spawn(async move {
@@ -138,8 +319,51 @@ impl<T: BotHandler> BotWebsocketClient<T> {
     }
 }
 
+#[async_trait]
+impl<T: BotHandler> BotTransport<T> for BotWebsocketClient<T> {
+    fn handler(&self) -> Arc<T> {
+        self.handler.clone()
+    }
+
+    fn disconnect(&mut self) {
+        BotWebsocketClient::disconnect(self);
+    }
+
+    async fn close(&mut self) {
+        self.closing.cancel();
+        if let Some(task) = self.supervisor_task.as_mut() {
+            let _ = time::timeout(CLOSE_TIMEOUT + Duration::from_secs(1), task).await;
+        }
+        BotWebsocketClient::disconnect(self);
+    }
+}
+
 impl<T: BotHandler> Drop for BotWebsocketClient<T> {
     fn drop(&mut self) {
         self.disconnect();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_request() -> Result<()> {
+        let config = ServerConfig {
+            access_token: Some("a b".to_string()),
+            ..Default::default()
+        };
+        let request = build_request(&config, "/api")?;
+
+        assert_eq!(request.uri().path(), "/api");
+        assert_eq!(request.uri().query(), Some("access_token=a+b"));
+        assert_eq!(request.headers()[header::AUTHORIZATION], "Bearer a b");
+
+        let request = build_request(&ServerConfig::default(), "/event")?;
+        assert_eq!(request.uri().query(), None);
+        assert!(!request.headers().contains_key(header::AUTHORIZATION));
+
+        Ok(())
+    }
+}
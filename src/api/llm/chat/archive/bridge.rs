@@ -3,19 +3,23 @@ use crate::{
         logic_import::{Message, Notice},
         message::{
             MessageReceive,
+            event_body::message_sent::MessageSent,
+            event_notice::Notify,
             message_body::{SegmentReceive, contact},
         },
+        network::BotClient,
     },
     api::llm::chat::{
         archive::{
             file_embedding::embedding_llm_file,
             identity::{IdentityGroup, IdentityPerson},
-            message_storage::MessageStorage,
+            resolve::Resolver,
         },
         file::LlmFile,
     },
 };
 use genai::chat::{Binary, ChatMessage, ContentPart, MessageContent};
+use serde::Serialize;
 use tracing::error;
 
 include!(concat!(env!("OUT_DIR"), "/face_data.rs"));
@@ -41,7 +45,13 @@ pub fn get_face_reference_message() -> ChatMessage {
     ChatMessage::system(parts) // 作为系统上下文发送
 }
 
-async fn llm_msg_from_segment_receive(segment: &SegmentReceive) -> ChatMessage {
+async fn llm_msg_from_segment_receive<T>(
+    segment: &SegmentReceive,
+    resolver: &mut Resolver<'_, T>,
+) -> ChatMessage
+where
+    T: BotClient + Send + Sync,
+{
     match segment {
         SegmentReceive::Text(e) => ChatMessage::user(e.text.clone()),
         SegmentReceive::Face(e) => {
@@ -156,32 +166,20 @@ async fn llm_msg_from_segment_receive(segment: &SegmentReceive) -> ChatMessage {
             e.title, e.content, e.lon, e.lat,
         )),
         SegmentReceive::Reply(e) => {
-            let msg_id = e.id.clone();
-            let content = vec![ContentPart::Text(format!("[回复消息 ID: {}]", msg_id))];
-            let msg_content = match MessageStorage::get(msg_id).await {
-                Some(c) => {
-                    let mut content = MessageContent::from(content);
-                    content.extend(c.content);
-                    content
-                }
-                None => MessageContent::from(content),
-            };
+            let content = vec![ContentPart::Text(format!("[回复消息 ID: {}]", e.id))];
+            let mut msg_content = MessageContent::from(content);
+            if let Some(c) = resolver.reply(&e.id).await {
+                msg_content.extend(c);
+            }
             ChatMessage::user(msg_content)
         }
         SegmentReceive::Forward(e) => {
-            let id = e.id.clone();
+            let id = &e.id;
             let content = vec![ContentPart::Text(format!("[转发消息 id: {id}]"))];
-
-            let msg = MessageStorage::get(id).await;
-
-            let msg_content = match msg {
-                Some(e) => {
-                    let mut content = MessageContent::from(content);
-                    content.extend(e.content);
-                    content
-                }
-                None => MessageContent::from(content),
-            };
+            let mut msg_content = MessageContent::from(content);
+            if let Some(c) = resolver.forward(id).await {
+                msg_content.extend(c);
+            }
             ChatMessage::user(msg_content)
         }
         SegmentReceive::Xml(e) => ChatMessage::user(format!("[XML消息 {}]", e.data)),
@@ -195,44 +193,95 @@ async fn llm_msg_from_segment_receive(segment: &SegmentReceive) -> ChatMessage {
                 Some(name),
             )))
         }
+        SegmentReceive::Unknown(e) => ChatMessage::user(format!(
+            "[未知消息段 类型: {} 内容: {}]",
+            e["type"].as_str().unwrap_or("未知"),
+            e["data"]
+        )),
     }
 }
 
-pub async fn llm_msg_from_message_receive(message: &MessageReceive) -> Vec<ChatMessage> {
+/// 逐段转换消息，引用与合并转发通过 `resolver` 展开
+pub async fn llm_msg_from_message_receive<T>(
+    message: &MessageReceive,
+    resolver: &mut Resolver<'_, T>,
+) -> Vec<ChatMessage>
+where
+    T: BotClient + Send + Sync,
+{
     match message {
         MessageReceive::Array(e) => {
             let mut result = Vec::with_capacity(e.len());
             for seg in e.iter() {
-                result.push(llm_msg_from_segment_receive(seg).await);
+                result.push(llm_msg_from_segment_receive(seg, resolver).await);
             }
             result
         }
         MessageReceive::Single(e) => {
-            vec![llm_msg_from_segment_receive(e).await]
+            vec![llm_msg_from_segment_receive(e, resolver).await]
         }
     }
 }
 
-pub async fn llm_msg_from_message(message: &Message) -> Vec<ChatMessage> {
+/// 把消息的元数据序列化后放在 `<data>` 中
+fn llm_msg_data<S: Serialize>(data: &S) -> ChatMessage {
+    let data = quick_xml::se::to_string(data).unwrap_or("未知消息".to_string());
+    ChatMessage::user(format!("<data>{}</data>", data))
+}
+
+/// 消息的元数据，放在 `<data>` 中
+pub fn llm_msg_meta(message: &Message) -> ChatMessage {
+    match message {
+        Message::Private(p) => llm_msg_data(p),
+        Message::Group(g) => llm_msg_data(g),
+    }
+}
+
+/// Bot 自己发送的消息的元数据
+pub fn llm_msg_sent_meta(message: &MessageSent) -> ChatMessage {
+    match message {
+        MessageSent::Private(p) => llm_msg_data(p),
+        MessageSent::Group(g) => llm_msg_data(g),
+    }
+}
+
+pub async fn llm_msg_from_message<T>(message: &Message, client: &T) -> Vec<ChatMessage>
+where
+    T: BotClient + Send + Sync,
+{
     archive_message_files(message).await;
+    let mut resolver = Resolver::new(client);
+    let mut ret = vec![llm_msg_meta(message)];
     match message {
         Message::Private(p) => {
-            let data = quick_xml::se::to_string(&p).unwrap_or("未知消息".to_string());
-            let mut ret = vec![ChatMessage::user(format!("<data>{}</data>", data))];
-            ret.extend(llm_msg_from_message_receive(&p.message).await);
-            ret
+            ret.extend(llm_msg_from_message_receive(&p.message, &mut resolver).await)
         }
         Message::Group(g) => {
-            let data = quick_xml::se::to_string(&g).unwrap_or("未知消息".to_string());
-            let mut ret = vec![ChatMessage::user(format!("<data>{}</data>", data))];
-            ret.extend(llm_msg_from_message_receive(&g.message).await);
-            ret
+            ret.extend(llm_msg_from_message_receive(&g.message, &mut resolver).await)
         }
     }
+    ret
+}
+
+/// Bot 自己发送的消息，元数据同样放在 `<data>` 中
+pub async fn llm_msg_from_message_sent<T>(message: &MessageSent, client: &T) -> Vec<ChatMessage>
+where
+    T: BotClient + Send + Sync,
+{
+    let mut resolver = Resolver::new(client);
+    let mut ret = vec![llm_msg_sent_meta(message)];
+    ret.extend(llm_msg_from_message_receive(message.get_message(), &mut resolver).await);
+    ret
 }
 
 pub async fn llm_msg_from_notice(notice: &Notice) -> ChatMessage {
-    ChatMessage::user(quick_xml::se::to_string(notice).unwrap_or("未知提示".to_string()))
+    match notice {
+        // 未知结构无法稳定转成 XML，直接给出原始 JSON
+        Notice::Unknown(e) | Notice::Notify(Notify::Unknown(e)) => {
+            ChatMessage::user(format!("[未知提示 {}]", e))
+        }
+        _ => ChatMessage::user(quick_xml::se::to_string(notice).unwrap_or("未知提示".to_string())),
+    }
 }
 
 async fn archive_message_file_inner(url: &str, name: String) {
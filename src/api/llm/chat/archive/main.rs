@@ -7,13 +7,14 @@ use crate::{
         logic_import::{Message, Notice},
         message::{
             api::{self, GetGroupInfo, GroupMemberInfo},
+            event_body::message_sent::MessageSent,
             event_notice::Notify,
         },
         network::BotClient,
         websocket::BotHandler,
     },
     api::llm::chat::archive::{
-        bridge::{llm_msg_from_message, llm_msg_from_notice},
+        bridge::{llm_msg_from_message, llm_msg_from_message_sent, llm_msg_from_notice},
         identity::{IdentityGroupUpdateSend, IdentityPersonUpdateSend, IdentityUpdate},
         message_storage::{MessageStorage, NoticeStorage},
     },
@@ -29,10 +30,20 @@ where
         Message::Private(p) => p.message_id.to_string(),
     };
 
-    let msg_content = llm_msg_from_message(&message).await;
+    let msg_content = llm_msg_from_message(&message, ctx.client.as_ref()).await;
     MessageStorage::save(id, msg_content).await;
 }
 
+/// 以 Napcat 返回的 message_id 归档 Bot 自己发出的消息
+pub async fn message_sent_archive<T>(message: &MessageSent, client: &T)
+where
+    T: BotClient + Send + Sync,
+{
+    let id = message.message_id().to_string();
+    let msg_content = llm_msg_from_message_sent(message, client).await;
+    MessageStorage::save_assistant(id, msg_content).await;
+}
+
 pub async fn notice_archive<T>(ctx: &mut Context<T, Notice>)
 where
     T: BotClient + BotHandler + std::fmt::Debug + Send + Sync + 'static,
@@ -53,7 +64,9 @@ where
             Notify::LuckyKing(e) => e.time,
             Notify::Honor(e) => e.time,
             Notify::Title(e) => e.time,
+            Notify::Unknown(e) => e["time"].as_i64().unwrap_or_default(),
         },
+        Notice::Unknown(e) => e["time"].as_i64().unwrap_or_default(),
     };
 
     let notice_content = llm_msg_from_notice(&notice).await;
@@ -1,5 +1,5 @@
 use crate::api::storage::ColdTable;
-use genai::chat::ChatMessage;
+use genai::chat::{ChatMessage, MessageContent};
 use serde::{Deserialize, Serialize};
 use std::{sync::LazyLock, time};
 
@@ -17,22 +17,41 @@ pub struct MessageStore {
 
 pub struct MessageStorage;
 
+/// 把逐段转换得到的消息合并为一段内容
+pub(super) fn merge_content(message: Vec<ChatMessage>) -> MessageContent {
+    let mut content = MessageContent::default();
+    for msg in message {
+        content.extend(msg.content);
+    }
+    content
+}
+
 impl MessageStorage {
     pub async fn get(key: String) -> Option<ChatMessage> {
         let msg = MESSAGE_DB.get(key).await.unwrap_or_default();
         msg.map(|m| m.msg)
     }
 
+    /// 保存用户发送的消息
     pub async fn save(key: String, message: Vec<ChatMessage>) {
-        let mut msg_contents = vec![];
-        for msg in message {
-            msg_contents.extend(msg.content);
-        }
+        Self::insert(key, ChatMessage::user(merge_content(message))).await;
+    }
+
+    /// 保存 Bot 自己发送的消息，作为 assistant 轮次
+    pub async fn save_assistant(key: String, message: Vec<ChatMessage>) {
+        Self::insert(key, ChatMessage::assistant(merge_content(message))).await;
+    }
+
+    pub async fn remove(key: String) {
+        let _ = MESSAGE_DB.remove(key).await;
+    }
+
+    async fn insert(key: String, msg: ChatMessage) {
         let _ = MESSAGE_DB
             .insert(
                 key,
                 MessageStore {
-                    msg: ChatMessage::user(msg_contents),
+                    msg,
                     timestamp: time::SystemTime::now()
                         .duration_since(time::UNIX_EPOCH)
                         .unwrap_or_default()
@@ -4,6 +4,7 @@ pub mod identity;
 mod main;
 pub mod memo_fragment;
 pub mod message_storage;
+pub mod resolve;
 pub mod search;
 
 pub use main::*;
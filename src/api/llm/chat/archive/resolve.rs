@@ -0,0 +1,248 @@
+use crate::{
+    abi::{
+        logic_import::Message,
+        message::{MessageReceive, api},
+        network::BotClient,
+        router::context::call_checked,
+    },
+    api::llm::chat::archive::{
+        bridge::{llm_msg_from_message_receive, llm_msg_meta},
+        message_storage::{MessageStorage, merge_content},
+    },
+    config,
+};
+use genai::chat::{ChatMessage, ContentPart, MessageContent};
+use std::{future::Future, pin::Pin};
+use tracing::warn;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 展开引用链与合并转发，按配置限制嵌套层数与带入的消息条数
+///
+/// 同一条消息中的所有引用与转发共享这些限制，避免一条消息带入过多上下文。
+pub struct Resolver<'a, T> {
+    client: &'a T,
+    /// 还能继续向下展开的层数
+    depth: usize,
+    /// 还能带入的消息条数
+    remaining: usize,
+}
+
+impl<'a, T> Resolver<'a, T>
+where
+    T: BotClient + Send + Sync,
+{
+    pub fn new(client: &'a T) -> Self {
+        let config = &config::current().llm;
+        Resolver {
+            client,
+            depth: config.context_max_depth,
+            remaining: config.context_max_messages,
+        }
+    }
+
+    /// 占用一条消息的额度，额度用完或已到最大层数时返回 false
+    fn take(&mut self) -> bool {
+        if self.depth == 0 || self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        true
+    }
+
+    /// 在下一层展开消息中的引用与转发
+    ///
+    /// 与 `llm_msg_from_message_receive` 互相递归，返回装箱的 future 以确定类型。
+    fn nested<'b>(&'b mut self, message: &'b MessageReceive) -> BoxFuture<'b, Vec<ChatMessage>> {
+        Box::pin(async move {
+            self.depth -= 1;
+            let ret = llm_msg_from_message_receive(message, self).await;
+            self.depth += 1;
+            ret
+        })
+    }
+
+    /// 引用的消息内容，优先使用归档，没有归档时通过 get_msg 获取并继续展开其中的引用
+    pub async fn reply(&mut self, id: &str) -> Option<MessageContent> {
+        if !self.take() {
+            return None;
+        }
+        // 归档中的消息在保存时已经展开过引用
+        if let Some(msg) = MessageStorage::get(id.to_string()).await {
+            return Some(msg.content);
+        }
+
+        let message_id = id.parse::<i64>().ok()?;
+        let message = call_checked(self.client, api::GetMsg::new(message_id), "获取消息")
+            .await
+            .and_then(|data| data.ok_or(anyhow::anyhow!("获取消息失败: 响应中没有数据")));
+        let message: Message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("无法获取引用的消息 {}: {:?}", id, e);
+                return None;
+            }
+        };
+        let body = match &message {
+            Message::Private(p) => &p.message,
+            Message::Group(g) => &g.message,
+        };
+        let mut content = llm_msg_meta(&message).content;
+        content.extend(merge_content(self.nested(body).await));
+        Some(content)
+    }
+
+    /// 合并转发的内容，逐条标出发送者，超出额度的部分只给出省略的条数
+    pub async fn forward(&mut self, id: &str) -> Option<MessageContent> {
+        if self.depth == 0 {
+            return None;
+        }
+        if let Some(msg) = MessageStorage::get(id.to_string()).await {
+            return self.take().then_some(msg.content);
+        }
+
+        let params = api::GetForwardMsg::new(id.to_string());
+        let nodes = match call_checked(self.client, params, "获取合并转发").await {
+            Ok(Some(data)) => data.nodes(),
+            Ok(None) => vec![],
+            Err(e) => {
+                warn!("无法获取合并转发 {}: {:?}", id, e);
+                return None;
+            }
+        };
+
+        let mut content = MessageContent::default();
+        let total = nodes.len();
+        let mut complete = true;
+        for (i, node) in nodes.iter().enumerate() {
+            if !self.take() {
+                content.push(ContentPart::Text(format!(
+                    "\n[其余 {} 条消息已省略]",
+                    total - i
+                )));
+                complete = false;
+                break;
+            }
+            content.push(ContentPart::Text(format!(
+                "\n[{}({})]: ",
+                node.nickname.as_deref().unwrap_or("未知"),
+                node.user_id.unwrap_or_default()
+            )));
+            content.extend(merge_content(self.nested(&node.message).await));
+        }
+        // 只归档完整展开的转发，被截断的内容留给下次额度充足时重新获取
+        if complete {
+            MessageStorage::save(id.to_string(), vec![ChatMessage::user(content.clone())]).await;
+        }
+        Some(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::FakeBot;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_reply_chain() {
+        let (bot, _subscribe) = FakeBot::new();
+        // 每条消息都引用 id 加一的消息，形成无限长的引用链
+        bot.script("get_msg", |params| {
+            let id = params["message_id"].as_i64().unwrap_or_default();
+            Ok(json!({
+                "message_type": "group",
+                "time": 1700000000,
+                "self_id": 10000,
+                "sub_type": "normal",
+                "message_id": id,
+                "group_id": 123,
+                "user_id": 789,
+                "anonymous": null,
+                "raw_message": "",
+                "font": 0,
+                "sender": { "user_id": 789, "nickname": "Quoted", "role": "member" },
+                "message": [
+                    { "type": "reply", "data": { "id": (id + 1).to_string() } },
+                    { "type": "text", "data": { "text": format!("第{}条", id) } },
+                ],
+            }))
+        });
+
+        let mut resolver = Resolver::new(bot.as_ref());
+        let content = resolver.reply("9100001").await.unwrap();
+        let text = content.texts().concat();
+        assert!(text.contains("第9100001条"));
+        assert!(text.contains("第9100003条"));
+        // 默认最多展开三层
+        assert!(!text.contains("第9100004条"));
+        let calls = bot.calls();
+        assert_eq!(calls.iter().filter(|c| c.action == "get_msg").count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_forward_limit() {
+        let (bot, _subscribe) = FakeBot::new();
+        bot.script("get_forward_msg", |_| {
+            let messages = (0..40)
+                .map(|i| {
+                    json!({
+                        "sender": { "user_id": 456, "nickname": "甲" },
+                        "content": [{ "type": "text", "data": { "text": format!("转发{}", i) } }],
+                    })
+                })
+                .collect::<Vec<_>>();
+            Ok(json!({ "messages": messages }))
+        });
+
+        let mut resolver = Resolver::new(bot.as_ref());
+        let content = resolver.forward("forward-res-id").await.unwrap();
+        let text = content.texts().concat();
+        assert!(text.contains("[甲(456)]: "));
+        assert!(text.contains("转发29"));
+        assert!(!text.contains("转发30"));
+        assert!(text.contains("[其余 10 条消息已省略]"));
+        assert!(
+            MessageStorage::get("forward-res-id".to_string())
+                .await
+                .is_none()
+        );
+
+        // 额度用完后不再展开
+        assert!(resolver.reply("9200001").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_forward_archive() {
+        let (bot, _subscribe) = FakeBot::new();
+        bot.script("get_forward_msg", |_| {
+            Ok(json!({
+                "messages": [{
+                    "sender": { "user_id": 456, "nickname": "乙" },
+                    "content": [{ "type": "text", "data": { "text": "归档的转发" } }],
+                }],
+            }))
+        });
+
+        let id = format!("forward-archive-{}", uuid::Uuid::new_v4());
+        let content = Resolver::new(bot.as_ref()).forward(&id).await.unwrap();
+        let archived = MessageStorage::get(id.clone()).await;
+        // 再次展开时直接使用归档
+        Resolver::new(bot.as_ref()).forward(&id).await.unwrap();
+        // 测试写入的是真实的归档表，先清理再检查结果
+        MessageStorage::remove(id.clone()).await;
+
+        assert!(content.texts().concat().contains("归档的转发"));
+        let archived = archived.expect("展开的转发没有被归档");
+        assert!(archived.content.texts().concat().contains("[乙(456)]: "));
+        let calls = bot.calls();
+        assert_eq!(
+            calls
+                .iter()
+                .filter(|c| c.action == "get_forward_msg")
+                .count(),
+            1
+        );
+        assert!(MessageStorage::get(id).await.is_none());
+    }
+}
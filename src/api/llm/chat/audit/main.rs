@@ -18,7 +18,7 @@ use crate::{
         },
         storage::ColdTable,
     },
-    config::LLM_AUDIT_DURATION_SECS,
+    config, shutdown,
 };
 use anyhow::Result;
 use futures::{SinkExt, StreamExt, channel::mpsc};
@@ -125,18 +125,19 @@ impl AuditTask {
         .await?;
 
         let ts = task.timestamp;
-        sleep_until_unix_timestamp(ts + LLM_AUDIT_DURATION_SECS + 3).await;
+        let duration = config::current().llm.audit_duration_secs;
+        sleep_until_unix_timestamp(ts + duration + 3).await;
         let src_msg = task.message.clone();
-        let before_msg_all = MessageStorage::get_range(ts - LLM_AUDIT_DURATION_SECS, ts).await;
+        let before_msg_all = MessageStorage::get_range(ts - duration, ts).await;
         let (before_id, before_msg) = before_msg_all
             .into_iter()
             .unzip::<String, ChatMessage, Vec<String>, Vec<ChatMessage>>();
-        let before_notice = NoticeStorage::get_range(ts - LLM_AUDIT_DURATION_SECS, ts).await;
-        let after_msg_all = MessageStorage::get_range(ts, ts + LLM_AUDIT_DURATION_SECS).await;
+        let before_notice = NoticeStorage::get_range(ts - duration, ts).await;
+        let after_msg_all = MessageStorage::get_range(ts, ts + duration).await;
         let (after_id, after_msg) = after_msg_all
             .into_iter()
             .unzip::<String, ChatMessage, Vec<String>, Vec<ChatMessage>>();
-        let after_notice = NoticeStorage::get_range(ts, ts + LLM_AUDIT_DURATION_SECS).await;
+        let after_notice = NoticeStorage::get_range(ts, ts + duration).await;
         let mut msg = Vec::with_capacity(
             before_msg.len() + before_notice.len() + after_msg.len() + after_notice.len() + 10,
         );
@@ -253,7 +254,14 @@ impl AuditTask {
         let data = data_clone.clone();
 
         tokio::spawn(async move {
-            while let Some(task) = rx.next().await {
+            let shutdown = shutdown::global();
+            // 停机后不再取新任务，未处理的任务保持 Pending，重启时重建
+            while let Some(task) = tokio::select! {
+                biased;
+                _ = shutdown.triggered() => None,
+                task = rx.next() => task,
+            } {
+                let _guard = shutdown.track();
                 match Self::process_task(task, data.clone()).await {
                     Ok(_) => {}
                     Err(e) => {
@@ -1,7 +1,9 @@
-use super::config::MODEL_MAP;
-use crate::api::llm::{
-    chat::file::LlmFile,
-    tool::{LlmPrompt, LlmVec, ask_as},
+use crate::{
+    api::llm::{
+        chat::file::LlmFile,
+        tool::{LlmPrompt, LlmVec, ask_as},
+    },
+    config,
 };
 use anyhow::{Result, anyhow};
 use genai::{
@@ -18,15 +20,17 @@ const CHAT_MODEL: &str = "gemini-flash-latest";
 const EMBED_MODEL: &str = "text-embedding-3-large";
 
 pub static CLIENT: LazyLock<Client> = LazyLock::new(|| {
-    // 1. 统一鉴权解析器：从 MODEL_MAP 匹配 API Key
+    // 1. 统一鉴权解析器：从配置的 llm.models 匹配 API Key，每次请求时读取以支持热重载
     let auth_resolver = AuthResolver::from_resolver_fn(|model_id: ModelIden| {
-        let config = MODEL_MAP
+        let llm = &config::current().llm;
+        let config = llm
+            .models
             .values()
             .find(|cfg| cfg.kind == model_id.adapter_kind);
 
         if let Some(cfg) = config {
             // 优先读取环境变量
-            if let Ok(key) = std::env::var(cfg.api_key_env) {
+            if let Ok(key) = std::env::var(&cfg.api_key_env) {
                 return Ok(Some(AuthData::from_single(key)));
             }
             // 兼容明文 sk- 写入
@@ -37,10 +41,10 @@ pub static CLIENT: LazyLock<Client> = LazyLock::new(|| {
         Ok(None)
     });
 
-    // 2. 统一路由解析器：根据模型名从 MODEL_MAP 映射 Base URL
+    // 2. 统一路由解析器：根据模型名从 llm.models 映射 Base URL
     let target_resolver = ServiceTargetResolver::from_resolver_fn(|mut target: ServiceTarget| {
-        if let Some(cfg) = MODEL_MAP.get(&*target.model.model_name) {
-            target.endpoint = Endpoint::from_static(cfg.base_url);
+        if let Some(cfg) = config::current().llm.models.get(&*target.model.model_name) {
+            target.endpoint = Endpoint::from_owned(cfg.base_url.clone());
         }
         Ok(target)
     });
@@ -1,6 +1,5 @@
 pub mod archive;
 pub mod audit;
-pub mod config;
 pub mod file;
 pub mod llm;
 pub mod message;
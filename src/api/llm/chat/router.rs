@@ -2,24 +2,34 @@ use crate::{
     abi::{
         Context,
         logic_import::{Message, Notice},
+        message::event_body::message_sent::MessageSent,
         network::BotClient,
+        router::settings,
         websocket::BotHandler,
     },
     api::llm::chat::{
         archive::{
-            identity_group_archive, identity_person_archive, message_archive, notice_archive,
+            identity_group_archive, identity_person_archive, message_archive, message_sent_archive,
+            notice_archive,
         },
         repeat::send_message_from_hot,
     },
+    config,
 };
 
 pub async fn handle_llm_message<T>(ctx: &mut Context<T, Message>)
 where
     T: BotClient + BotHandler + std::fmt::Debug + Send + Sync + 'static,
 {
-    message_archive(ctx).await;
-    identity_person_archive(ctx).await;
-    identity_group_archive(ctx).await;
+    let settings = settings::get(ctx.target);
+    if settings.archive {
+        message_archive(ctx).await;
+        identity_person_archive(ctx).await;
+        identity_group_archive(ctx).await;
+    }
+    if !settings.llm_enabled() {
+        return;
+    }
 
     //L0: 命中回复
     if send_message_from_hot(ctx).await.is_ok() {
@@ -37,5 +47,20 @@ pub async fn handle_llm_notice<T>(ctx: &mut Context<T, Notice>)
 where
     T: BotClient + BotHandler + std::fmt::Debug + Send + Sync + 'static,
 {
-    notice_archive(ctx).await;
+    if settings::get(ctx.target).archive {
+        notice_archive(ctx).await;
+    }
+}
+
+/// Bot 自己发出的消息只做归档，与用户消息共用会话的归档开关
+pub async fn handle_llm_message_sent<T>(message: &MessageSent, client: &T)
+where
+    T: BotClient + Send + Sync,
+{
+    if !config::current().features.llm_chat {
+        return;
+    }
+    if settings::get(message.get_target()).archive {
+        message_sent_archive(message, client).await;
+    }
 }
@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 
-use super::config::MODEL_MAP;
+use crate::config;
 use anyhow::{Result, anyhow};
 use genai::{
     Client, ModelIden, ServiceTarget,
@@ -16,13 +16,15 @@ pub static CLIENT: LazyLock<Client> = LazyLock::new(|| {
     // 1. AuthResolver
     let auth_resolver = AuthResolver::from_resolver_fn(|model_id: ModelIden| {
         // 关键：我们要找的是匹配当前 adapter_kind 的配置
-        let config = MODEL_MAP
+        let llm = &config::current().llm;
+        let config = llm
+            .models
             .values()
             .find(|cfg| cfg.kind == model_id.adapter_kind);
 
         if let Some(cfg) = config {
             // 尝试从环境变量读取
-            if let Ok(key) = std::env::var(cfg.api_key_env) {
+            if let Ok(key) = std::env::var(&cfg.api_key_env) {
                 return Ok(Some(AuthData::from_single(key)));
             }
             // 如果环境变量不存在，直接把 api_key_env 字符串本身当作 Key (兼容你目前的写法)
@@ -36,8 +38,8 @@ pub static CLIENT: LazyLock<Client> = LazyLock::new(|| {
     // 2. ServiceTargetResolver (修正逻辑)
     let target_resolver = ServiceTargetResolver::from_resolver_fn(|mut target: ServiceTarget| {
         // 注意：genai 的 model_name 可能是全称，这里用 get 匹配
-        if let Some(cfg) = MODEL_MAP.get(&*target.model.model_name) {
-            target.endpoint = Endpoint::from_static(cfg.base_url);
+        if let Some(cfg) = config::current().llm.models.get(&*target.model.model_name) {
+            target.endpoint = Endpoint::from_owned(cfg.base_url.clone());
         }
         Ok(target)
     });
@@ -1,4 +1,3 @@
-mod config;
 mod llm;
 mod r#type;
 
@@ -14,6 +14,7 @@ use std::{path::Path, sync::Arc};
 use tokio::task;
 
 static COLD_ENGINE: LazyLock<Arc<Database>> = LazyLock::new(|| {
+    crate::config::ensure_dir(BASE_DATA_DIR);
     let path = Path::new(concatcp!(BASE_DATA_DIR, "/", BASE));
     let db = Database::builder().create(path).unwrap();
     Arc::new(db)
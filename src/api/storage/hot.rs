@@ -11,7 +11,10 @@ use redb::{Database, ReadableDatabase, ReadableTable, TableDefinition};
 use serde::{Serialize, de::DeserializeOwned};
 use std::sync::LazyLock;
 use std::{path::Path, sync::Arc};
-use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::{
+    mpsc::{self, UnboundedSender},
+    oneshot,
+};
 
 enum StoreOp {
     Upsert {
@@ -23,6 +26,8 @@ enum StoreOp {
         table_name: &'static str,
         key: Bytes,
     },
+    /// 之前的写入提交后回复
+    Flush(oneshot::Sender<()>),
 }
 
 pub mod send_engine {
@@ -70,6 +75,14 @@ pub mod send_engine {
 
 static HOT_ENGINE: LazyLock<StorageEngine> = LazyLock::new(StorageEngine::create);
 
+/// 等待此前所有写入提交到磁盘，停机前调用
+pub async fn flush() -> Result<()> {
+    let (tx, rx) = oneshot::channel();
+    HOT_ENGINE.send(StoreOp::Flush(tx))?;
+    rx.await?;
+    Ok(())
+}
+
 struct StorageEngine {
     pub sender: UnboundedSender<StoreOp>,
     pub db: Arc<Database>,
@@ -78,6 +91,7 @@ struct StorageEngine {
 impl StorageEngine {
     pub fn create() -> Self {
         let path = Path::new(concatcp!(BASE_DATA_DIR, "/", BASE));
+        crate::config::ensure_dir(BASE_DATA_DIR);
 
         let db: Database = Database::builder().create(path).unwrap();
 
@@ -86,21 +100,30 @@ impl StorageEngine {
         let (tx, mut rx) = mpsc::unbounded_channel::<StoreOp>();
 
         let db_in = db_arc.clone();
-        tokio::task::spawn_blocking(move || {
-            let db = db_in;
-
-            while let Some(first_op) = rx.blocking_recv() {
-                let write_txn = db.begin_write().unwrap();
-
-                process_op(&write_txn, first_op);
-
-                while let Ok(next_op) = rx.try_recv() {
-                    process_op(&write_txn, next_op);
+        // 写入线程随进程存活，不属于任何运行时
+        // 放进 spawn_blocking 会让创建它的运行时在关闭时一直等待，测试中每个用例的运行时都会卡住
+        std::thread::Builder::new()
+            .name("hot-storage".to_string())
+            .spawn(move || {
+                let db = db_in;
+
+                let mut acks = Vec::new();
+                while let Some(first_op) = rx.blocking_recv() {
+                    let write_txn = db.begin_write().unwrap();
+
+                    process_op(&write_txn, first_op, &mut acks);
+
+                    while let Ok(next_op) = rx.try_recv() {
+                        process_op(&write_txn, next_op, &mut acks);
+                    }
+
+                    write_txn.commit().unwrap();
+                    for ack in acks.drain(..) {
+                        let _ = ack.send(());
+                    }
                 }
-
-                write_txn.commit().unwrap();
-            }
-        });
+            })
+            .unwrap();
 
         Self {
             sender: tx,
@@ -159,7 +182,7 @@ impl StorageEngine {
     }
 }
 
-fn process_op(txn: &redb::WriteTransaction, op: StoreOp) {
+fn process_op(txn: &redb::WriteTransaction, op: StoreOp, acks: &mut Vec<oneshot::Sender<()>>) {
     match op {
         StoreOp::Upsert {
             table_name,
@@ -175,6 +198,7 @@ fn process_op(txn: &redb::WriteTransaction, op: StoreOp) {
             let mut table = txn.open_table(definition).unwrap();
             table.remove(key.as_ref()).unwrap();
         }
+        StoreOp::Flush(ack) => acks.push(ack),
     }
 }
 
@@ -214,4 +238,11 @@ where
     pub fn get(&self, key: &K) -> Option<Arc<V>> {
         self.cache.get(key).map(|v| v.clone())
     }
+
+    pub fn keys(&self) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.cache.iter().map(|e| e.key().clone()).collect()
+    }
 }
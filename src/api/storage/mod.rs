@@ -11,6 +11,7 @@ pub use file::File;
 pub use file::FileBackend;
 pub use file::FileStorage;
 pub use hot::HotTable;
+pub use hot::flush as flush_hot;
 pub use temp::TempFile;
 pub use vector::{HasEmbedding, VectorSearchEngine};
 
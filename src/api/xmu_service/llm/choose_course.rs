@@ -1,5 +1,5 @@
 use crate::api::{
-    llm::tool::{LlmI64, LlmOption, LlmPrompt, ask_as},
+    llm::tool::{LlmI64, LlmOption, LlmPrompt, LlmVec, ask_as},
     network::SessionClient,
     xmu_service::lnt::{MyCourses, RecentlyVisitedCourses},
 };
@@ -12,6 +12,10 @@ use serde::{Deserialize, Serialize};
 pub struct CourseChoiceResponse {
     #[prompt("如果找到符合要求的课程就返回课程ID; 如果没找到指定的课程就是 null")]
     pub course_id: LlmOption<LlmI64>,
+    #[prompt(
+        "如果无法确定用户指的是哪一门课程，返回最多 3 个可能的课程ID供用户选择；已经确定或没有相关课程时为 null"
+    )]
+    pub candidates: LlmOption<LlmVec<LlmI64>>,
 }
 
 pub struct ChooseCourse;
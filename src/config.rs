@@ -1,19 +1,28 @@
-use std::fs;
-
-use serde::Serialize;
-
-const CONFIG: Config = Config {
-    napcat: ServerConfig {
-        host: "127.0.0.1",
-        port: 3008,
-        access_token: None,
-        reconnect_interval_secs: 10,
-    },
-    bot: BotConfig {
-        command_prefix: "/",
-    },
+use std::{
+    collections::HashMap,
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime},
 };
 
+use anyhow::{Context, Result, bail};
+use arc_swap::{ArcSwap, Guard};
+use genai::adapter::AdapterKind;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+/// 默认配置文件路径，可通过环境变量 `XMU_BOT_CONFIG` 指定
+pub const CONFIG_PATH: &str = "config.toml";
+const CONFIG_PATH_ENV: &str = "XMU_BOT_CONFIG";
+/// 覆盖配置项的环境变量前缀，如 `XMU_BOT__NAPCAT__PORT=3001` 覆盖 `napcat.port`
+const ENV_PREFIX: &str = "XMU_BOT__";
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+static CONFIG: LazyLock<ArcSwap<Config>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Config::default()));
+
 pub fn ensure_dir(path: &'static str) -> &'static str {
     fs::create_dir_all(path).expect("Failed to create necessary directory");
     path
@@ -21,50 +30,481 @@ pub fn ensure_dir(path: &'static str) -> &'static str {
 
 pub const DATA_DIR: &str = "./data";
 
-pub const fn get_command_prefix() -> &'static str {
-    CONFIG.bot.command_prefix
+/// 当前生效的配置
+///
+/// 热重载后新取到的 Guard 即为新配置，不要跨 await 长期持有。
+pub fn current() -> Guard<Arc<Config>> {
+    CONFIG.load()
+}
+
+pub fn config_path() -> PathBuf {
+    std::env::var_os(CONFIG_PATH_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(CONFIG_PATH))
+}
+
+/// 启动时加载配置，文件不存在时使用默认配置
+pub fn init(path: &Path) -> Result<()> {
+    let config = load(path, std::env::vars())?;
+    CONFIG.store(Arc::new(config));
+    Ok(())
+}
+
+/// 读取配置文件并应用环境变量覆盖，再校验配置是否合法
+pub fn load(path: &Path, vars: impl IntoIterator<Item = (String, String)>) -> Result<Config> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            warn!("配置文件 {} 不存在，使用默认配置", path.display());
+            String::new()
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("读取配置文件 {} 失败", path.display()));
+        }
+    };
+    parse(&text, vars)
+}
+
+pub fn parse(text: &str, vars: impl IntoIterator<Item = (String, String)>) -> Result<Config> {
+    let mut table: toml::Table = toml::from_str(text).context("配置文件格式错误")?;
+    apply_env_overrides(&mut table, vars)?;
+    let config: Config = toml::Value::Table(table)
+        .try_into()
+        .context("配置项不符合要求")?;
+    config.validate()?;
+    Ok(config)
 }
 
-pub const fn get_napcat_config() -> ServerConfig {
-    CONFIG.napcat
+/// 环境变量中的 `__` 表示层级，键名统一转为小写；
+/// 值按 TOML 解析，解析失败时视为字符串
+fn apply_env_overrides(
+    table: &mut toml::Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<()> {
+    for (key, raw) in vars {
+        let Some(path) = key.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let keys = path
+            .split("__")
+            .map(|k| k.to_ascii_lowercase())
+            .collect::<Vec<_>>();
+        if keys.iter().any(|k| k.is_empty()) {
+            bail!("环境变量 {} 的格式不正确", key);
+        }
+
+        let value = toml::from_str::<toml::Table>(&format!("v = {}", raw))
+            .ok()
+            .and_then(|mut t| t.remove("v"))
+            .unwrap_or(toml::Value::String(raw));
+
+        let (last, parents) = keys.split_last().unwrap();
+        let mut current = &mut *table;
+        for k in parents {
+            let entry = current
+                .entry(k.as_str())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            current = match entry {
+                toml::Value::Table(t) => t,
+                _ => bail!("环境变量 {} 覆盖的配置项 {} 不是表", key, k),
+            };
+        }
+        current.insert(last.clone(), value);
+    }
+    Ok(())
+}
+
+/// 定期检查配置文件的修改时间，变化后重新加载可热重载的部分
+///
+/// 新配置不合法时保留旧配置并记录错误。
+pub fn watch(path: PathBuf) {
+    tokio::spawn(async move {
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut last: Option<SystemTime> = modified(&path);
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now = modified(&path);
+            if now == last {
+                continue;
+            }
+            last = now;
+
+            match load(&path, std::env::vars()) {
+                Ok(new) => {
+                    let merged = merge(&current(), new);
+                    CONFIG.store(Arc::new(merged));
+                    info!("配置文件 {} 已重新加载", path.display());
+                }
+                Err(e) => error!("重新加载配置文件失败，继续使用旧配置: {:?}", e),
+            }
+        }
+    });
+}
+
+/// napcat 与 web 在启动时就已建立连接或监听端口，修改后需重启才能生效，
+/// 其余部分直接替换
+fn merge(old: &Config, new: Config) -> Config {
+    if old.napcat != new.napcat || old.web != new.web {
+        warn!("napcat 与 web 配置的修改需要重启后才能生效");
+    }
+    Config {
+        napcat: old.napcat.clone(),
+        web: old.web.clone(),
+        ..new
+    }
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub napcat: ServerConfig,
     pub bot: BotConfig,
+    pub features: FeatureConfig,
+    pub requests: RequestConfig,
+    pub outbound: OutboundConfig,
+    pub web: WebConfig,
+    pub llm: LlmConfig,
 }
 
-#[derive(Serialize, Debug, Clone)]
+impl Config {
+    pub fn validate(&self) -> Result<()> {
+        if self.napcat.host.is_empty() {
+            bail!("napcat.host 不能为空");
+        }
+        if self.napcat.port == 0 {
+            bail!("napcat.port 不能为 0");
+        }
+        if self.napcat.mode == ConnectMode::Http {
+            self.napcat
+                .webhook_addr
+                .parse::<SocketAddr>()
+                .context("napcat.webhook_addr 不是有效的监听地址")?;
+        }
+
+        let prefix = &self.bot.command_prefix;
+        if prefix.is_empty() || prefix.contains(char::is_whitespace) {
+            bail!("bot.command_prefix 不能为空或包含空白字符");
+        }
+
+        if !self.requests.student_id_pattern.is_empty() {
+            regex::Regex::new(&self.requests.student_id_pattern)
+                .context("requests.student_id_pattern 不是有效的正则表达式")?;
+        }
+
+        let outbound = &self.outbound;
+        if outbound.global_rate <= 0.0 || outbound.target_rate <= 0.0 {
+            bail!("outbound.global_rate 与 outbound.target_rate 必须大于 0");
+        }
+        if outbound.global_burst < 1.0 || outbound.target_burst < 1.0 {
+            bail!("outbound.global_burst 与 outbound.target_burst 不能小于 1");
+        }
+        if outbound.max_message_chars == 0 {
+            bail!("outbound.max_message_chars 不能为 0");
+        }
+
+        self.web
+            .listen
+            .parse::<SocketAddr>()
+            .context("web.listen 不是有效的监听地址")?;
+        if !self.web.url.starts_with("http://") && !self.web.url.starts_with("https://") {
+            bail!("web.url 必须以 http:// 或 https:// 开头");
+        }
+
+        for (name, model) in &self.llm.models {
+            if model.base_url.is_empty() {
+                bail!("llm.models.{} 的 base_url 不能为空", name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 与 Napcat 的连接方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectMode {
+    /// Bot 主动连接 Napcat 的 `/event` 与 `/api`
+    #[default]
+    Forward,
+    /// Bot 在 host:port 上监听，由 Napcat 反向连接进来
+    Reverse,
+    /// 通过 Napcat 的 HTTP 服务调用 API，事件由 webhook_addr 上的 HTTP 上报接收
+    Http,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct ServerConfig {
-    pub host: &'static str,
+    pub mode: ConnectMode,
+    pub host: String,
     pub port: u16,
-    pub access_token: Option<&'static str>,
+    pub access_token: Option<String>,
     pub reconnect_interval_secs: u64,
+    pub webhook_addr: String,
+    /// 将收到的原始帧录制到 data/record 下，用于线上问题复现
+    pub record_frames: bool,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         ServerConfig {
-            host: "127.0.0.1",
-            port: 3001,
+            mode: ConnectMode::Forward,
+            host: "127.0.0.1".to_string(),
+            port: 3008,
             access_token: None,
             reconnect_interval_secs: 10,
+            webhook_addr: "0.0.0.0:3009".to_string(),
+            record_frames: false,
         }
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
 pub struct BotConfig {
-    pub command_prefix: &'static str,
+    pub command_prefix: String,
+    /// 初始 Bot 管理员，始终拥有所有指令的权限
+    pub admins: Vec<i64>,
+    /// 全局黑名单用户
+    pub blacklist: Vec<i64>,
+    /// 群白名单，为 None 时处理所有群
+    pub group_allowlist: Option<Vec<i64>>,
+    /// 需要忽略的其他 Bot 账号
+    pub ignore_bots: Vec<i64>,
 }
 
 impl Default for BotConfig {
     fn default() -> Self {
         BotConfig {
-            command_prefix: "/",
+            command_prefix: "/".to_string(),
+            admins: Vec::new(),
+            blacklist: Vec::new(),
+            group_allowlist: None,
+            ignore_bots: Vec::new(),
         }
     }
 }
 
-pub const LLM_AUDIT_DURATION_SECS: u64 = 60;
+/// 功能开关
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureConfig {
+    /// 非指令消息与通知交给 LLM 处理
+    pub llm_chat: bool,
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        FeatureConfig { llm_chat: true }
+    }
+}
+
+/// 好友申请与加群申请的处理规则，黑名单用户的申请总是自动拒绝
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RequestConfig {
+    /// 自动同意好友申请，关闭时转交 Bot 管理员处理
+    pub auto_approve_friend: bool,
+    /// 加群验证信息匹配该正则时自动同意，为空时全部转交 Bot 管理员处理
+    pub student_id_pattern: String,
+    /// 自动拒绝加群申请时附带的理由
+    pub reject_reason: String,
+}
+
+impl Default for RequestConfig {
+    fn default() -> Self {
+        RequestConfig {
+            auto_approve_friend: true,
+            student_id_pattern: r"(^|\D)\d{14}(\D|$)".to_string(),
+            reject_reason: "申请已被拒绝".to_string(),
+        }
+    }
+}
+
+/// 发送消息的限流与重试，避免短时间内大量发送触发风控
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutboundConfig {
+    /// 全局每秒可发送的消息数
+    pub global_rate: f64,
+    /// 全局允许的突发消息数
+    pub global_burst: f64,
+    /// 单个群或私聊每秒可发送的消息数
+    pub target_rate: f64,
+    pub target_burst: f64,
+    /// 视为被限流的 retcode，收到后退避重试
+    ///
+    /// 只应填写确定表示“未发送”的 retcode，否则消息实际已发出时重试会造成重复发送。
+    pub rate_limited_retcodes: Vec<u16>,
+    pub max_retries: u32,
+    /// 第一次重试前等待的毫秒数，之后每次翻倍
+    pub retry_base_ms: u64,
+    /// 单条消息中文本的最大字数，超出时拆分为多条发送
+    pub max_message_chars: usize,
+}
+
+impl Default for OutboundConfig {
+    fn default() -> Self {
+        OutboundConfig {
+            global_rate: 5.0,
+            global_burst: 20.0,
+            target_rate: 1.0,
+            target_burst: 5.0,
+            // Napcat 的所有发送失败（被禁言、消息实际已发出但超时等）都返回 1200，默认不重试
+            rate_limited_retcodes: Vec::new(),
+            max_retries: 3,
+            retry_base_ms: 500,
+            max_message_chars: 3000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebConfig {
+    /// 对外访问的地址，用于生成文件下载链接
+    pub url: String,
+    pub listen: String,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        WebConfig {
+            url: "https://zzy.vintces.icu".to_string(),
+            listen: "0.0.0.0:3080".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LlmConfig {
+    /// 审核时向前、向后各取多少秒内的消息作为上下文
+    pub audit_duration_secs: u64,
+    /// 展开引用链与合并转发时最多嵌套的层数
+    pub context_max_depth: usize,
+    /// 单条消息展开引用与合并转发时最多带入的消息条数
+    pub context_max_messages: usize,
+    /// 模型名到端点与厂商的映射
+    pub models: HashMap<String, ModelConfig>,
+}
+
+impl Default for LlmConfig {
+    fn default() -> Self {
+        LlmConfig {
+            audit_duration_secs: 60,
+            context_max_depth: 3,
+            context_max_messages: 30,
+            models: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ModelConfig {
+    pub kind: AdapterKind,
+    pub base_url: String,
+    /// 存放 API Key 的环境变量名，也兼容直接填写 sk- 开头的 Key
+    pub api_key_env: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = parse(
+            r##"
+            [napcat]
+            mode = "reverse"
+            port = 3001
+
+            [bot]
+            command_prefix = "#"
+            admins = [123]
+
+            [llm.models."gemini-flash-latest"]
+            kind = "Gemini"
+            base_url = "https://example.com/"
+            api_key_env = "GEMINI_API_KEY"
+            "##,
+            [],
+        )
+        .unwrap();
+        assert_eq!(config.napcat.mode, ConnectMode::Reverse);
+        assert_eq!(config.napcat.port, 3001);
+        assert_eq!(config.napcat.host, "127.0.0.1");
+        assert_eq!(config.bot.command_prefix, "#");
+        assert_eq!(config.bot.admins, vec![123]);
+        assert_eq!(
+            config.llm.models["gemini-flash-latest"].kind,
+            AdapterKind::Gemini
+        );
+
+        // 空文件即默认配置
+        assert_eq!(parse("", []).unwrap().bot, BotConfig::default());
+    }
+
+    #[test]
+    fn test_example() {
+        let config = load(Path::new("config.example.toml"), []).unwrap();
+        assert_eq!(config.llm.models.len(), 2);
+    }
+
+    #[test]
+    fn test_env_override() {
+        let config = parse(
+            "[bot]\ncommand_prefix = \"#\"",
+            vars(&[
+                ("XMU_BOT__NAPCAT__PORT", "3001"),
+                ("XMU_BOT__NAPCAT__ACCESS_TOKEN", "secret"),
+                ("XMU_BOT__BOT__BLACKLIST", "[1, 2]"),
+                ("XMU_BOT__FEATURES__LLM_CHAT", "false"),
+                ("PATH", "/usr/bin"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(config.napcat.port, 3001);
+        assert_eq!(config.napcat.access_token.as_deref(), Some("secret"));
+        assert_eq!(config.bot.command_prefix, "#");
+        assert_eq!(config.bot.blacklist, vec![1, 2]);
+        assert!(!config.features.llm_chat);
+
+        assert!(parse("", vars(&[("XMU_BOT__BOT__COMMAND_PREFIX__X", "1")])).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(parse("[bot]\ncommand_prefix = \"\"", []).is_err());
+        assert!(parse("[napcat]\nport = 0", []).is_err());
+        assert!(parse("[web]\nlisten = \"localhost\"", []).is_err());
+        assert!(parse("[napcat]\nmode = \"udp\"", []).is_err());
+        assert!(parse("[requests]\nstudent_id_pattern = \"(\"", []).is_err());
+        assert!(parse("[outbound]\ntarget_rate = 0", []).is_err());
+        // 拼错的配置项直接报错，而不是静默忽略
+        assert!(parse("[bot]\ncommand_prefx = \"#\"", []).is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let old = Config::default();
+        let mut new = Config::default();
+        new.napcat.port = 1;
+        new.bot.command_prefix = "#".to_string();
+
+        let merged = merge(&old, new);
+        assert_eq!(merged.napcat.port, old.napcat.port);
+        assert_eq!(merged.bot.command_prefix, "#");
+    }
+}
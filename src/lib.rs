@@ -3,4 +3,5 @@ pub mod api;
 pub mod config;
 pub mod logger;
 pub mod logic;
+pub mod shutdown;
 pub mod web;
@@ -0,0 +1,69 @@
+use super::BuildHelp;
+use crate::abi::{
+    logic_import::*,
+    message::from_str,
+    router::{
+        middleware,
+        permission::{add_bot_admin, bot_admins, remove_bot_admin},
+    },
+};
+use anyhow::{Result, anyhow, bail};
+
+#[derive(CommandArgs)]
+pub struct AdminArgs {
+    action: String,
+    value: Option<String>,
+}
+
+fn parse_user_id(value: Option<&str>) -> Result<i64> {
+    let mention = value
+        .ok_or(anyhow!("缺少 QQ 号"))?
+        .parse::<Mention>()
+        .map_err(|e| anyhow!("不是有效的 QQ 号: {}", e))?;
+    Ok(mention.0)
+}
+
+#[handler(msg_type=Message,command="admin",echo_cmd=true,permission=BotAdmin,args=AdminArgs,
+help_msg=r#"用法:/admin <add|remove> <QQ号> 或 /admin list 或 /admin maintenance <on|off>
+功能: 管理 Bot 管理员与维护模式"#)]
+pub async fn admin(ctx: Context, args: AdminArgs) -> Result<()> {
+    let operator = ctx.sender.user_id.ok_or(anyhow!("获取用户ID失败"))?;
+    let value = args.value.as_deref();
+
+    match args.action.as_str() {
+        "add" => {
+            let id = parse_user_id(value)?;
+            add_bot_admin(id, operator)?;
+            ctx.send_message_async(from_str(format!("已将 {} 设为 Bot 管理员", id)));
+        }
+        "remove" => {
+            let id = parse_user_id(value)?;
+            remove_bot_admin(id)?;
+            ctx.send_message_async(from_str(format!("已移除 Bot 管理员 {}", id)));
+        }
+        "list" => {
+            let list = bot_admins()
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            ctx.send_message_async(from_str(format!("Bot 管理员:\n{}", list)));
+        }
+        "maintenance" => {
+            let on = match value {
+                Some("on") => true,
+                Some("off") => false,
+                _ => bail!("用法: /admin maintenance <on|off>"),
+            };
+            middleware::set_maintenance(on);
+            ctx.send_message_async(from_str(if on {
+                "已开启维护模式"
+            } else {
+                "已关闭维护模式"
+            }));
+        }
+        _ => bail!("未知的子命令，请使用 /help 查看用法"),
+    }
+
+    Ok(())
+}
@@ -4,11 +4,8 @@ use crate::{
         logic_import::*,
         message::{MessageSend, from_str},
     },
-    api::xmu_service::{
-        llm::{ChooseCourse, ChooseFiles},
-        lnt::FileUrl,
-    },
-    logic::helper::get_client_or_err,
+    api::xmu_service::{llm::ChooseFiles, lnt::FileUrl},
+    logic::helper::{choose_course, get_client_or_err},
     web::file::task::ExposeFileTask,
 };
 use anyhow::{anyhow, bail};
@@ -16,22 +13,17 @@ use std::sync::Arc;
 use tracing::trace;
 
 #[handler(msg_type=Message,command="download",echo_cmd=true,
+aliases=["下载", "dl"],cooldown="30s",daily_quota=20,scope="user",
 help_msg=r#"用法:/download <描述>
 <描述>:描述课程及文件，后端使用LLM进行智能识别查询，如果没有提到使用哪个 文件那么就会下载这门课的全部文件
 功能: 下载指定课程文件"#)]
 pub async fn download(ctx: Context) -> Result<()> {
     let client = get_client_or_err(&ctx).await?;
-    let msg_text = ctx.get_message_text();
-    let course_id = {
-        let course = ChooseCourse::get_from_client(&client, msg_text).await?;
-        trace!("返回课程选择结果：");
-        trace!(?course);
-        course.course_id
-    }
-    .ok_or(anyhow!("未找到课程，请更加清晰的阐释课程的名称"))?;
+    let msg_text = ctx.message_text.clone();
+    let course_id = choose_course(&mut ctx, &client, &msg_text).await?;
     let files = {
         trace!("选择课程 ID: {}", course_id);
-        let files = ChooseFiles::get_from_client(&client, msg_text, *course_id).await?;
+        let files = ChooseFiles::get_from_client(&client, &*msg_text, course_id).await?;
         trace!("返回文件选择结果：");
         trace!(?files);
         files.files
@@ -1,7 +1,7 @@
 use super::BuildHelp;
 use crate::abi::logic_import::*;
 
-#[handler(msg_type=Message,command="echo",echo_cmd=true,
+#[handler(msg_type=Message,command="echo",echo_cmd=true,aliases=["复读"],
 help_msg=r#"用法:/echo <内容>
 <内容>:你想让我重复的话
 功能:用于测试系统可用性"#)]
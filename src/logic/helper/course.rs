@@ -0,0 +1,83 @@
+use anyhow::{Result, bail};
+
+use crate::{
+    abi::{
+        Context,
+        logic_import::Message,
+        message::{MessageType, from_str},
+        network::BotClient,
+        websocket::BotHandler,
+    },
+    api::{
+        network::SessionClient,
+        xmu_service::{llm::ChooseCourse, lnt::MyCourses},
+    },
+};
+use std::{fmt, time::Duration};
+use tracing::trace;
+
+/// 等待用户选择课程的时长
+const CHOOSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn parse_choice(text: &str, count: usize) -> Option<usize> {
+    text.trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|i| (1..=count).contains(i))
+}
+
+/// 根据描述选择课程，LLM 无法确定时列出候选课程，让用户回复序号选择
+pub async fn choose_course<T>(
+    ctx: &mut Context<T, Message>,
+    client: &SessionClient,
+    text: &str,
+) -> Result<i64>
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+{
+    let choice = ChooseCourse::get_from_client(client, text).await?;
+    trace!("返回课程选择结果：");
+    trace!(?choice);
+    if let Some(course_id) = *choice.course_id {
+        return Ok(*course_id);
+    }
+
+    let candidate_ids = choice
+        .candidates
+        .iter()
+        .flat_map(|ids| ids.iter().map(|id| **id))
+        .collect::<Vec<_>>();
+    if candidate_ids.is_empty() {
+        bail!("未找到课程，请更加清晰的阐释课程的名称");
+    }
+    let candidates = MyCourses::get_from_client(client)
+        .await?
+        .courses
+        .into_iter()
+        .filter(|course| candidate_ids.contains(&course.id))
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        bail!("未找到课程，请更加清晰的阐释课程的名称");
+    }
+
+    let list = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, course)| format!("{}. {}", i + 1, course.name))
+        .collect::<Vec<_>>()
+        .join("\n");
+    ctx.send_message_async(from_str(format!(
+        "无法确定是哪门课程，请回复序号选择:\n{}\n回复“取消”放弃",
+        list
+    )));
+
+    let count = candidates.len();
+    let reply = ctx
+        .wait_for_next_message(
+            move |msg| parse_choice(&msg.get_text(), count).is_some(),
+            CHOOSE_TIMEOUT,
+        )
+        .await?;
+    let index = parse_choice(&reply.get_text(), count).unwrap_or(1);
+    Ok(candidates[index - 1].id)
+}
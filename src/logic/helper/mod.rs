@@ -1,3 +1,5 @@
+mod course;
 mod session;
 
+pub use course::*;
 pub use session::*;
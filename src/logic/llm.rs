@@ -5,12 +5,18 @@ use crate::{
 
 #[handler(msg_type=Message)]
 pub async fn llm_message(ctx: Context) -> Result<()> {
+    if !config::current().features.llm_chat {
+        return Ok(());
+    }
     handle_llm_message(&mut ctx).await;
     Ok(())
 }
 
 #[handler(msg_type=Notice)]
 pub async fn llm_notice(ctx: Context) -> Result<()> {
+    if !config::current().features.llm_chat {
+        return Ok(());
+    }
     handle_llm_notice(&mut ctx).await;
     Ok(())
 }
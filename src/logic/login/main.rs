@@ -8,7 +8,7 @@ use std::sync::LazyLock;
 
 pub static DATA: LazyLock<HotTable<i64, LoginData>> = LazyLock::new(|| HotTable::new("login"));
 
-#[handler(msg_type=Message,command="login",echo_cmd=true,
+#[handler(msg_type=Message,command="login",echo_cmd=true,aliases=["登录"],
 help_msg=r#"用法:/login
 功能:使用扫码方式登录学校系统"#)]
 pub async fn login(ctx: Context) -> Result<()> {
@@ -33,7 +33,7 @@ pub async fn login(ctx: Context) -> Result<()> {
     Ok(())
 }
 
-#[handler(msg_type=Message,command="logout",echo_cmd=true,
+#[handler(msg_type=Message,command="logout",echo_cmd=true,aliases=["注销"],
 help_msg=r#"用法:/logout
 功能:删除登录数据"#)]
 pub async fn logout(ctx: Context) -> Result<()> {
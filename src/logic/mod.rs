@@ -1,14 +1,20 @@
+mod admin;
 mod download;
 mod echo;
 mod helper;
 mod llm;
 mod login;
+mod request;
+mod settings;
 mod test;
 
 use crate::abi::logic_import::*;
 
 pub trait BuildHelp {
     const HELP_MSG: &'static str;
+    /// 指令名与全部别名
+    const COMMANDS: &'static [&'static str];
+    const PERMISSION: Permission;
 }
 
 register_handler_with_help!(
@@ -18,6 +24,13 @@ register_handler_with_help!(
         login::LogoutHandler,
         download::DownloadHandler,
         test::TestHandler,
+        admin::AdminHandler,
+        settings::GroupConfigHandler,
+        request::RequestAdminHandler,
     ],
-    other = [llm::LlmMessageHandler, llm::LlmNoticeHandler,]
+    other = [
+        llm::LlmMessageHandler,
+        llm::LlmNoticeHandler,
+        request::AddRequestHandler,
+    ]
 );
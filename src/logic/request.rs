@@ -0,0 +1,354 @@
+use super::BuildHelp;
+use crate::{
+    abi::{
+        echo::Echo,
+        logic_import::*,
+        message::{api, event_body::request::SubType, from_str},
+        router::permission::bot_admins,
+    },
+    api::storage::HotTable,
+    config::Config,
+};
+use anyhow::{Result, anyhow, bail};
+use arc_swap::ArcSwap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::{
+    Arc, LazyLock,
+    atomic::{AtomicI64, Ordering},
+};
+use tracing::{error, info, trace, warn};
+
+/// 等待 Bot 管理员处理的请求，键为转交时分配的编号
+static PENDING: LazyLock<HotTable<i64, PendingRequest>> =
+    LazyLock::new(|| HotTable::new("pending_request"));
+
+static NEXT_ID: LazyLock<AtomicI64> =
+    LazyLock::new(|| AtomicI64::new(PENDING.keys().into_iter().max().unwrap_or(0) + 1));
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Friend,
+    /// 他人申请加入 Bot 所在的群
+    GroupAdd,
+    /// 他人邀请 Bot 入群
+    GroupInvite,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingRequest {
+    pub kind: RequestKind,
+    pub flag: String,
+    pub user_id: i64,
+    pub group_id: Option<i64>,
+    pub comment: String,
+}
+
+impl PendingRequest {
+    fn from_event(request: &Request) -> Option<Self> {
+        match request {
+            Request::Friend(f) => Some(PendingRequest {
+                kind: RequestKind::Friend,
+                flag: f.flag.clone(),
+                user_id: f.user_id,
+                group_id: None,
+                comment: f.comment.clone(),
+            }),
+            Request::Group(g) => Some(PendingRequest {
+                kind: match g.sub_type {
+                    SubType::Add => RequestKind::GroupAdd,
+                    SubType::Invite => RequestKind::GroupInvite,
+                },
+                flag: g.flag.clone(),
+                user_id: g.user_id,
+                group_id: Some(g.group_id),
+                comment: g.comment.clone(),
+            }),
+            Request::Unknown(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PendingRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let group_id = self.group_id.unwrap_or_default();
+        match self.kind {
+            RequestKind::Friend => write!(f, "好友申请\nQQ: {}", self.user_id)?,
+            RequestKind::GroupAdd => write!(f, "加群申请\n群: {}\nQQ: {}", group_id, self.user_id)?,
+            RequestKind::GroupInvite => {
+                write!(f, "入群邀请\n群: {}\n邀请人: {}", group_id, self.user_id)?
+            }
+        }
+        write!(f, "\n验证信息: {}", self.comment)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Approve,
+    Reject,
+    /// 转交 Bot 管理员处理
+    Forward,
+}
+
+/// 编译好的学号正则及其来源，配置重载后模式变化时重新编译
+static STUDENT_ID: LazyLock<ArcSwap<(String, Regex)>> =
+    LazyLock::new(|| ArcSwap::from_pointee((String::new(), Regex::new("").unwrap())));
+
+fn student_id_regex(pattern: &str) -> Option<Arc<(String, Regex)>> {
+    let cached = STUDENT_ID.load_full();
+    if cached.0 == pattern {
+        return Some(cached);
+    }
+    // 正则已在加载配置时校验过
+    let re = Regex::new(pattern)
+        .map_err(|e| error!("学号正则无效: {:?}", e))
+        .ok()?;
+    let compiled = Arc::new((pattern.to_string(), re));
+    STUDENT_ID.store(compiled.clone());
+    Some(compiled)
+}
+
+fn decide(request: &PendingRequest, config: &Config) -> Decision {
+    if config.bot.blacklist.contains(&request.user_id) {
+        return Decision::Reject;
+    }
+    let rules = &config.requests;
+    match request.kind {
+        RequestKind::Friend if rules.auto_approve_friend => Decision::Approve,
+        RequestKind::GroupAdd if !rules.student_id_pattern.is_empty() => {
+            match student_id_regex(&rules.student_id_pattern) {
+                Some(re) if re.1.is_match(&request.comment) => Decision::Approve,
+                _ => Decision::Forward,
+            }
+        }
+        _ => Decision::Forward,
+    }
+}
+
+/// 同意或拒绝请求，`reason` 只对加群申请与入群邀请有效
+async fn reply_request<T: BotClient>(
+    client: &T,
+    request: &PendingRequest,
+    approve: bool,
+    reason: Option<String>,
+) -> Result<()> {
+    let flag = request.flag.clone();
+    let call = match request.kind {
+        RequestKind::Friend => {
+            let params = api::SetFriendAddRequest::new(flag, approve);
+            client.call_api(params, Echo::new()).await?
+        }
+        RequestKind::GroupAdd | RequestKind::GroupInvite => {
+            let reason = if approve { None } else { reason };
+            let params = api::SetGroupAddRequest::new(flag, approve, reason);
+            client.call_api(params, Echo::new()).await?
+        }
+    };
+    let res = call.wait_echo().await?;
+    trace!(?res);
+    match res.status {
+        api::Status::Ok => Ok(()),
+        api::Status::Failed => Err(anyhow!(
+            "处理请求失败: {:?}",
+            res.message.unwrap_or("未知错误".to_string())
+        )),
+        api::Status::Async => Err(anyhow!("处理请求异步处理中")),
+    }
+}
+
+#[handler(msg_type=Request)]
+pub async fn add_request(ctx: Context) -> Result<()> {
+    let Some(request) = PendingRequest::from_event(&ctx.message) else {
+        return Ok(());
+    };
+
+    // 请求事件没有可以回复的对象，出错时只记录日志
+    let config = config::current().clone();
+    let result = match decide(&request, &config) {
+        Decision::Approve => {
+            info!("自动同意请求: {:?}", request);
+            reply_request(ctx.client.as_ref(), &request, true, None).await
+        }
+        Decision::Reject => {
+            info!("自动拒绝黑名单用户的请求: {:?}", request);
+            let reason = config.requests.reject_reason.clone();
+            reply_request(ctx.client.as_ref(), &request, false, Some(reason)).await
+        }
+        Decision::Forward => forward_to_admins(&ctx, request, &config).await,
+    };
+    if let Err(e) = result {
+        error!("处理请求事件失败: {:?}", e);
+    }
+
+    Ok(())
+}
+
+async fn forward_to_admins<T, M>(
+    ctx: &Context<T, M>,
+    request: PendingRequest,
+    config: &Config,
+) -> Result<()>
+where
+    T: BotClient + BotHandler + fmt::Debug + Send + Sync + 'static,
+    M: MessageType + fmt::Debug + Send + Sync + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let text = format!(
+        "[#{id}] {request}\n使用 {prefix}request approve {id} 同意，{prefix}request reject {id} [理由] 拒绝",
+        prefix = config.bot.command_prefix,
+    );
+    PENDING.insert(id, Arc::new(request))?;
+
+    let admins = bot_admins();
+    if admins.is_empty() {
+        warn!("没有 Bot 管理员，请求 #{} 将保持待处理", id);
+    }
+    for admin in admins {
+        if let Err(e) = ctx
+            .send_message_to(Target::Private(admin), from_str(text.clone()))
+            .await
+        {
+            error!("向管理员 {} 转交请求 #{} 失败: {:?}", admin, id, e);
+        }
+    }
+    Ok(())
+}
+
+#[derive(CommandArgs)]
+pub struct RequestArgs {
+    action: String,
+    id: Option<i64>,
+    #[arg(rest)]
+    reason: Option<String>,
+}
+
+#[handler(msg_type=Message,command="request",echo_cmd=true,permission=BotAdmin,args=RequestArgs,
+help_msg=r#"用法:/request list 或 /request approve <编号> 或 /request reject <编号> [理由]
+功能: 处理转交给管理员的好友申请、加群申请与入群邀请"#)]
+pub async fn request_admin(ctx: Context, args: RequestArgs) -> Result<()> {
+    if args.action == "list" {
+        let mut ids = PENDING.keys();
+        if ids.is_empty() {
+            ctx.send_message_async(from_str("没有待处理的请求"));
+            return Ok(());
+        }
+        ids.sort_unstable();
+        for id in ids {
+            if let Some(request) = PENDING.get(&id) {
+                ctx.send_message_async(from_str(format!("[#{}] {}", id, request)));
+            }
+        }
+        return Ok(());
+    }
+
+    let approve = match args.action.as_str() {
+        "approve" => true,
+        "reject" => false,
+        _ => bail!("未知的子命令，请使用 /help 查看用法"),
+    };
+    let id = args.id.ok_or(anyhow!("缺少请求编号"))?;
+    let request = PENDING
+        .get(&id)
+        .ok_or(anyhow!("没有编号为 {} 的待处理请求", id))?;
+    let reason = args
+        .reason
+        .unwrap_or_else(|| config::current().requests.reject_reason.clone());
+
+    reply_request(ctx.client.as_ref(), &request, approve, Some(reason)).await?;
+    PENDING.remove(&id)?;
+    ctx.send_message_async(from_str(format!(
+        "已{}请求 #{}",
+        if approve { "同意" } else { "拒绝" },
+        id
+    )));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::fake::{FAKE_SELF_ID, FakeBot};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn group_add(user_id: i64, comment: &str) -> PendingRequest {
+        PendingRequest {
+            kind: RequestKind::GroupAdd,
+            flag: "flag".to_string(),
+            user_id,
+            group_id: Some(123),
+            comment: comment.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_decide() {
+        let mut config = Config::default();
+        config.bot.blacklist = vec![666];
+
+        assert_eq!(
+            decide(&group_add(456, "学号22920232201234"), &config),
+            Decision::Approve
+        );
+        assert_eq!(
+            decide(&group_add(456, "我是新生"), &config),
+            Decision::Forward
+        );
+        assert_eq!(
+            decide(&group_add(456, "229202322012345"), &config),
+            Decision::Forward
+        );
+        assert_eq!(
+            decide(&group_add(666, "22920232201234"), &config),
+            Decision::Reject
+        );
+
+        let friend = PendingRequest {
+            kind: RequestKind::Friend,
+            ..group_add(456, "")
+        };
+        assert_eq!(decide(&friend, &config), Decision::Approve);
+        config.requests.auto_approve_friend = false;
+        assert_eq!(decide(&friend, &config), Decision::Forward);
+
+        let invite = PendingRequest {
+            kind: RequestKind::GroupInvite,
+            ..group_add(456, "22920232201234")
+        };
+        assert_eq!(decide(&invite, &config), Decision::Forward);
+
+        // 重载后的新模式立即生效
+        config.requests.student_id_pattern = "^新生$".to_string();
+        assert_eq!(decide(&group_add(456, "新生"), &config), Decision::Approve);
+        assert_eq!(
+            decide(&group_add(456, "学号22920232201234"), &config),
+            Decision::Forward
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_request() -> anyhow::Result<()> {
+        let bot = FakeBot::spawn_router();
+        bot.inject_json(json!({
+            "post_type": "request",
+            "request_type": "group",
+            "time": 1700000000,
+            "self_id": FAKE_SELF_ID,
+            "sub_type": "add",
+            "group_id": 123,
+            "user_id": 456,
+            "comment": "问题：学号\n答案：22920232201234",
+            "flag": "abc",
+        }))?;
+
+        let call = bot
+            .wait_for("set_group_add_request", Duration::from_secs(5))
+            .await
+            .expect("没有处理加群申请");
+        assert_eq!(call.params, json!({ "flag": "abc", "approve": true }));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use super::BuildHelp;
+use crate::abi::{logic_import::*, message::from_str, router::settings};
+use anyhow::{Result, bail};
+
+#[derive(CommandArgs)]
+pub struct ConfigArgs {
+    action: String,
+    key: Option<String>,
+    #[arg(rest)]
+    value: Option<String>,
+}
+
+#[handler(msg_type=Message,command="config",echo_cmd=true,permission=GroupAdmin,args=ConfigArgs,
+help_msg=r#"用法:/config show 或 /config set <设置项> <值>
+设置项: llm <on|off>, archive <on|off>, commands <指令1,指令2|all>, style <forward|plain|quote>, quiet <起始小时-结束小时|off>
+功能: 查看或修改本群的设置"#)]
+pub async fn group_config(ctx: Context, args: ConfigArgs) -> Result<()> {
+    let Target::Group(group_id) = ctx.target else {
+        bail!("该指令只能在群聊中使用");
+    };
+
+    match args.action.as_str() {
+        "show" => {
+            let current = settings::get(ctx.target);
+            ctx.send_message_async(from_str(format!("本群设置:\n{}", current)));
+        }
+        "set" => {
+            let (Some(key), Some(value)) = (args.key.as_deref(), args.value.as_deref()) else {
+                bail!("用法: /config set <设置项> <值>");
+            };
+            let mut new = (*settings::get(ctx.target)).clone();
+            new.set(key, value, super::command_name)?;
+            settings::save(group_id, new).await?;
+            ctx.send_message_async(from_str(format!("已将 {} 设为 {}", key, value)));
+        }
+        _ => bail!("未知的子命令，请使用 /help 查看用法"),
+    }
+
+    Ok(())
+}
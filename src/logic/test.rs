@@ -1,31 +1,22 @@
 use super::BuildHelp;
 use crate::{
     abi::{logic_import::*, message::from_str},
-    api::xmu_service::{
-        llm::ChooseCourse,
-        lnt::{Distribute, Exams},
-    },
-    logic::helper::get_client_or_err,
+    api::xmu_service::lnt::{Distribute, Exams},
+    logic::helper::{choose_course, get_client_or_err},
 };
-use anyhow::anyhow;
 use tracing::trace;
 
-#[handler(msg_type=Message,command="test",echo_cmd=true,
+#[handler(msg_type=Message,command="test",echo_cmd=true,aliases=["测试"],
+cooldown="30s",daily_quota=20,scope="user",
 help_msg=r#"用法:/test <描述>
 <描述>:描述课程，后端使用LLM进行智能识别查询指定的课程的测试信息
 功能: 查询指定课程的测试信息"#)]
 pub async fn test(ctx: Context) -> Result<()> {
     let client = get_client_or_err(&ctx).await?;
-    let msg_text = ctx.get_message_text();
-    let course_id = {
-        let course = ChooseCourse::get_from_client(&client, msg_text).await?;
-        trace!("返回课程选择结果：");
-        trace!(?course);
-        course.course_id
-    }
-    .ok_or(anyhow!("未找到课程，请更加清晰的阐释课程的名称"))?;
+    let msg_text = ctx.message_text.clone();
+    let course_id = choose_course(&mut ctx, &client, &msg_text).await?;
 
-    let exam_data = Exams::get_from_client(&client, *course_id).await?;
+    let exam_data = Exams::get_from_client(&client, course_id).await?;
 
     for exam in exam_data.exams {
         trace!("测试信息：{:?}", exam);
@@ -42,20 +33,21 @@ pub async fn test(ctx: Context) -> Result<()> {
     Ok(())
 }
 
+// get_test 尚未注册到路由
+#[allow(dead_code)]
+#[derive(CommandArgs)]
+pub struct GetTestArgs {
+    id: i64,
+}
+
 #[handler(msg_type=Message,command="get_test",echo_cmd=true,
+cooldown="30s",daily_quota=20,scope="user",args=GetTestArgs,
 help_msg=r#"用法:/get_test <ID>
 <ID>: 查询小测的ID，通过 /test 命令获取
 功能: 查询指定小测的内容"#)]
-pub async fn get_test(ctx: Context) -> Result<()> {
+pub async fn get_test(ctx: Context, args: GetTestArgs) -> Result<()> {
     let client = get_client_or_err(&ctx).await?;
-    let id = ctx
-        .get_message_text()
-        .split_whitespace()
-        .collect::<String>()
-        .parse::<i64>()
-        .map_err(|e| anyhow!("不是有效的ID: {}\n可以通过/test 获取ID", e))?;
-
-    let distribute = Distribute::get_from_client(&client, id).await?;
+    let distribute = Distribute::get_from_client(&client, args.id).await?;
 
     for subject in distribute.subjects {
         trace!("题目信息：{:?}", subject);
@@ -4,10 +4,28 @@ static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 use xmu_assistant_bot::*;
 
 use anyhow::Result;
-use tracing::level_filters::LevelFilter;
-use xmu_assistant_bot::abi::router::handler::Router;
+use std::fmt;
+use std::time::Duration;
+use tokio::time;
+use tracing::{info, level_filters::LevelFilter, warn};
+#[cfg(feature = "testing")]
+use xmu_assistant_bot::abi::replay::{self, ReplaySpeed};
+use xmu_assistant_bot::abi::{
+    network::BotClient,
+    router::{
+        handler::{NapcatRouter, Router},
+        middleware::{Blacklist, GroupAllowList, IgnoreBots, Maintenance, TraceSpan},
+        settings,
+    },
+    scheduler,
+    websocket::BotHandler,
+};
+use xmu_assistant_bot::api::storage::flush_hot;
+use xmu_assistant_bot::config::ConnectMode;
 
 const LOG_PATH: &str = "logs";
+/// 停机时等待 Web 服务与存储落盘的最长时间
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -16,13 +34,80 @@ async fn main() -> Result<()> {
 
     let _guard = logger::init_logger(LOG_PATH, LevelFilter::TRACE);
 
-    let mut router = abi::run(config::get_napcat_config())
-        .await
-        .expect("Failed to initialize ABI and connect to Napcat");
+    let config_path = config::config_path();
+    config::init(&config_path).expect("加载配置文件失败");
+    config::watch(config_path);
 
-    web::start().await?;
+    #[cfg(feature = "testing")]
+    if replay_from_args().await? {
+        return Ok(());
+    }
 
+    let napcat_config = config::current().napcat.clone();
+    match napcat_config.mode {
+        ConnectMode::Http => serve(abi::run_http(napcat_config).await).await,
+        ConnectMode::Forward | ConnectMode::Reverse => serve(abi::run(napcat_config).await).await,
+    }
+}
+
+/// 用法: xmu_assistant_bot --replay <录制文件> [--fast]，需要启用 testing 特性
+///
+/// 没有指定 --replay 时返回 false，继续正常启动。
+#[cfg(feature = "testing")]
+async fn replay_from_args() -> Result<bool> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let Some(pos) = args.iter().position(|a| a == "--replay") else {
+        return Ok(false);
+    };
+    let path = args.get(pos + 1).expect("--replay 需要指定录制文件");
+    let speed = if args.iter().any(|a| a == "--fast") {
+        ReplaySpeed::Fast
+    } else {
+        ReplaySpeed::Original
+    };
+    let _bot = replay::replay(path, speed).await?;
+    // 保持运行，等待 handler 处理完回放的事件
+    tokio::signal::ctrl_c().await?;
+    Ok(true)
+}
+
+async fn serve<T>(router: Result<NapcatRouter<T>>) -> Result<()>
+where
+    T: BotClient + BotHandler + fmt::Debug,
+{
+    let mut router = router.expect("Failed to initialize ABI and connect to Napcat");
+
+    router
+        .add_middleware(TraceSpan)
+        .add_middleware(Maintenance)
+        .add_middleware(IgnoreBots::from_config())
+        .add_middleware(Blacklist::from_config())
+        .add_middleware(GroupAllowList::from_config());
+
+    shutdown::listen();
+    settings::load().await;
+    scheduler::start(router.get_client()).await;
+    let web = web::start().await?;
+
+    // 收到停机信号后返回，此后不再分发新的事件
     router.run().await;
 
+    let shutdown = shutdown::global();
+    if !shutdown.drain(shutdown::DRAIN_TIMEOUT).await {
+        warn!(
+            "等待 {} 秒后仍有任务未结束，强制退出",
+            shutdown::DRAIN_TIMEOUT.as_secs()
+        );
+    }
+    if time::timeout(CLOSE_TIMEOUT, web).await.is_err() {
+        warn!("Web 服务未能及时关闭");
+    }
+    router.close().await;
+    match time::timeout(CLOSE_TIMEOUT, flush_hot()).await {
+        Ok(Ok(())) => info!("存储已落盘"),
+        Ok(Err(e)) => warn!("存储落盘失败: {:?}", e),
+        Err(_) => warn!("存储落盘超时"),
+    }
+
     Ok(())
 }
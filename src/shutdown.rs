@@ -0,0 +1,119 @@
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::{TaskTracker, task_tracker::TaskTrackerToken};
+use tracing::{error, info};
+
+/// 停机时等待处理中的 handler 的最长时间
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+static GLOBAL: LazyLock<Shutdown> = LazyLock::new(Shutdown::new);
+
+/// 停机信号与需要在停机前完成的任务
+///
+/// 触发后路由不再接收新的事件，`drain` 等待已登记的任务结束。
+#[derive(Debug, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    tasks: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// 等待停机信号
+    pub async fn triggered(&self) {
+        self.token.cancelled().await;
+    }
+
+    /// 与停机信号绑定的 token，可交给需要自行退出的后台任务
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// 启动一个停机前需要等待其完成的任务
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(task)
+    }
+
+    /// 在返回值存活期间视为有一个未完成的任务，用于不便单独 spawn 的处理过程
+    pub fn track(&self) -> TaskTrackerToken {
+        self.tasks.token()
+    }
+
+    /// 等待登记的任务全部结束，超时返回 false
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.tasks.close();
+        info!("等待 {} 个处理中的任务结束", self.tasks.len());
+        time::timeout(timeout, self.tasks.wait()).await.is_ok()
+    }
+}
+
+pub fn global() -> &'static Shutdown {
+    &GLOBAL
+}
+
+/// 收到 Ctrl-C 或 SIGTERM 时触发全局停机
+pub fn listen() {
+    tokio::spawn(async {
+        let ctrl_c = tokio::signal::ctrl_c();
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            match signal(SignalKind::terminate()) {
+                Ok(mut term) => {
+                    tokio::select! {
+                        _ = ctrl_c => {}
+                        _ = term.recv() => {}
+                    }
+                }
+                Err(e) => {
+                    error!("监听 SIGTERM 失败: {:?}", e);
+                    let _ = ctrl_c.await;
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = ctrl_c.await;
+
+        info!("收到停机信号，停止接收新的事件");
+        global().trigger();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain() {
+        let shutdown = Shutdown::new();
+        shutdown.spawn(time::sleep(Duration::from_millis(50)));
+        let guard = shutdown.track();
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger();
+        shutdown.triggered().await;
+        assert!(!shutdown.drain(Duration::from_millis(100)).await);
+
+        drop(guard);
+        assert!(shutdown.drain(Duration::from_millis(100)).await);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     api::storage::{self, FileStorage, HotTable},
-    web::{URL, file::expose::ON_QUEUE},
+    config,
+    web::file::expose::ON_QUEUE,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -92,6 +93,6 @@ impl ExposeFileTask {
     }
 
     pub fn get_url(&self) -> String {
-        format!("{}/file/task/{}", URL, self.id)
+        format!("{}/file/task/{}", config::current().web.url, self.id)
     }
 }
@@ -1,24 +1,36 @@
+use crate::{
+    abi::{
+        network,
+        router::outbound::{self, OutboundStats},
+    },
+    config, shutdown,
+};
 use anyhow::Result;
-use axum::{Router, routing::get};
+use axum::{Json, Router, routing::get};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::error;
 
 pub mod file;
 pub mod md;
 
 use file::file_router;
 
-const URL: &str = "https://zzy.vintces.icu";
-const LOCAL: &str = "0.0.0.0:3080";
-
-pub async fn start() -> Result<()> {
+/// 启动 Web 服务，收到停机信号后不再接受新连接并等待已有请求完成
+pub async fn start() -> Result<JoinHandle<()>> {
     let app = router();
 
-    let listener = tokio::net::TcpListener::bind(LOCAL).await?;
-
-    tokio::spawn(async move {
-        axum::serve(listener, app).await.unwrap();
-    });
+    let listen = config::current().web.listen.clone();
+    let listener = tokio::net::TcpListener::bind(listen).await?;
 
-    Ok(())
+    Ok(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown::global().triggered())
+            .await
+        {
+            error!("Web 服务异常退出: {:?}", e);
+        }
+    }))
 }
 
 fn router() -> Router {
@@ -28,9 +40,30 @@ fn router() -> Router {
 }
 
 fn main_router(router: Router) -> Router {
-    router.route("/status", get(status_handler))
+    router
+        .route("/status", get(status_handler))
+        .route("/status/outbound", get(outbound_handler))
+        .route("/status/events", get(events_handler))
 }
 
 async fn status_handler() -> &'static str {
     "Web API is running"
 }
+
+/// 发送队列的长度与延迟
+async fn outbound_handler() -> Json<OutboundStats> {
+    Json(outbound::stats())
+}
+
+#[derive(Serialize, Debug)]
+struct EventStats {
+    /// 自启动以来收到的未知事件结构数量
+    unknown_shapes: u64,
+}
+
+/// 事件解析的统计
+async fn events_handler() -> Json<EventStats> {
+    Json(EventStats {
+        unknown_shapes: network::unknown_shape_count(),
+    })
+}